@@ -0,0 +1,43 @@
+//! Benchmarks for decoding large events that arrive slowly, in many small chunks.
+//!
+//! The time per byte should stay the same as the event size grows.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures_codec::{BytesMut, Decoder};
+use sse_codec::SSECodec;
+
+/// Build a single event with one `data:` line of `size` bytes.
+fn large_event(size: usize) -> Vec<u8> {
+    let mut input = b"data:".to_vec();
+    input.resize(size, b'x');
+    input.extend_from_slice(b"\n\n");
+    input
+}
+
+/// Feed `input` to a fresh decoder `chunk_size` bytes at a time, returning the number of events.
+fn decode_in_chunks(input: &[u8], chunk_size: usize) -> usize {
+    let mut codec = SSECodec::new();
+    let mut buffer = BytesMut::new();
+    let mut events = 0;
+    for chunk in input.chunks(chunk_size) {
+        buffer.extend_from_slice(chunk);
+        while codec.decode(&mut buffer).unwrap().is_some() {
+            events += 1;
+        }
+    }
+    events
+}
+
+fn slow_large_event(c: &mut Criterion) {
+    let mut group = c.benchmark_group("slow_large_event");
+    for size in [64 * 1024, 256 * 1024, 1024 * 1024, 4 * 1024 * 1024] {
+        let input = large_event(size);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| assert_eq!(decode_in_chunks(input, 64), 1));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, slow_large_event);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//! Benchmarks for encoding events of different sizes.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures_codec::{BytesMut, Encoder};
+use sse_codec::{Event, SSECodec};
+
+/// A typical small event, with an ID, an event type and a line of JSON.
+fn small_event() -> Event {
+    Event::message("update", r#"{"id":1234,"status":"ok"}"#, "1234")
+}
+
+/// An event with `lines` lines of data.
+fn multi_line_event(lines: usize) -> Event {
+    let data = vec!["0123456789abcdef0123456789abcdef"; lines].join("\n");
+    Event::message("message", &data, None)
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    let events = [
+        ("small", small_event()),
+        ("lines/16", multi_line_event(16)),
+        ("lines/1024", multi_line_event(1024)),
+    ];
+    for (name, event) in events.iter() {
+        group.throughput(Throughput::Bytes(event.encoded_len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("reused_buffer", name),
+            event,
+            |b, event| {
+                let mut codec = SSECodec::new();
+                let mut buffer = BytesMut::new();
+                b.iter_batched(
+                    || event.clone(),
+                    |event| {
+                        buffer.clear();
+                        codec.encode(event, &mut buffer).unwrap();
+                    },
+                    BatchSize::SmallInput,
+                );
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("new_buffer", name), event, |b, event| {
+            let mut codec = SSECodec::new();
+            b.iter_batched(
+                || event.clone(),
+                |event| {
+                    let mut buffer = BytesMut::new();
+                    codec.encode(event, &mut buffer).unwrap();
+                    buffer
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
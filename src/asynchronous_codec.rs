@@ -0,0 +1,217 @@
+//! Support for [`asynchronous-codec`](https://crates.io/crates/asynchronous-codec), the maintained
+//! fork of `futures_codec`.
+//!
+//! Requires the `asynchronous-codec` feature. This module has the same type aliases and functions
+//! as the crate root, so switching backends only requires changing the import path.
+//!
+//! # Examples
+//! ```rust
+//! use sse_codec::{asynchronous_codec::decode_stream, Event};
+//! use futures::stream::TryStreamExt; // for try_next()
+//!
+//! # async_std::task::block_on(async {
+//! let input: &[u8] = b"data: hello\n\n";
+//! let mut events = decode_stream(input);
+//! assert_eq!(events.try_next().await?, Some(Event::message("message", "hello", None)));
+//! # Ok::<(), sse_codec::Error>(())
+//! # }).unwrap();
+//! ```
+use crate::{
+    AutoId, BytesDecoder, BytesEvent, Error, Event, Frame, FrameEncoder, IdGenerator, Parsed,
+    SSECodec, StreamState,
+};
+use ::asynchronous_codec::{Decoder, Encoder, FramedRead, FramedWrite};
+use bytes1::BytesMut;
+use futures_io::{AsyncRead, AsyncWrite};
+
+impl Decoder for SSECodec {
+    type Item = Event;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decode_foreign(src)?.map(Parsed::into_event))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decode_foreign_eof(src)?.map(Parsed::into_event))
+    }
+}
+
+impl Decoder for BytesDecoder {
+    type Item = BytesEvent;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .codec
+            .decode_foreign(src)?
+            .map(Parsed::into_bytes_event))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .codec
+            .decode_foreign_eof(src)?
+            .map(Parsed::into_bytes_event))
+    }
+}
+
+impl Encoder for SSECodec {
+    type Item<'a> = Event;
+    type Error = Error;
+
+    fn encode(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_event(item, dest)
+    }
+}
+
+impl Encoder for FrameEncoder {
+    type Item<'a> = Frame;
+    type Error = Error;
+
+    fn encode(&mut self, item: Frame, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.codec.encode_frame(item, dest)
+    }
+}
+
+impl<G: IdGenerator> Encoder for AutoId<G> {
+    type Item<'a> = Event;
+    type Error = Error;
+
+    fn encode(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_event(item, dest)
+    }
+}
+
+impl<R: AsyncRead, D: Decoder + StreamState> StreamState for FramedRead<R, D> {
+    fn last_event_id(&self) -> Option<&str> {
+        self.decoder().last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.decoder().reconnection_time()
+    }
+}
+
+/// Type of a decoding stream, returned from `asynchronous_codec::decode_stream()`.
+pub type DecodeStream<R> = FramedRead<R, SSECodec>;
+
+/// Type of a decoding stream, returned from `asynchronous_codec::decode_bytes_stream()`.
+pub type BytesDecodeStream<R> = FramedRead<R, BytesDecoder>;
+
+/// Type of an encoding stream, returned from `asynchronous_codec::encode_stream()`.
+pub type EncodeStream<W> = FramedWrite<W, SSECodec>;
+
+/// Type of an encoding stream for `Frame`s, returned from `asynchronous_codec::encode_frame_stream()`.
+pub type FrameEncodeStream<W> = FramedWrite<W, FrameEncoder>;
+
+/// Type of an encoding stream that assigns event IDs, returned from
+/// `asynchronous_codec::encode_auto_id_stream()`.
+pub type AutoIdEncodeStream<W, G> = FramedWrite<W, AutoId<G>>;
+
+/// Parse messages from an `AsyncRead`, returning a stream of `Event`s.
+pub fn decode_stream<R: AsyncRead>(input: R) -> DecodeStream<R> {
+    decode_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from an `AsyncRead` using a configured codec, returning a stream of `Event`s.
+pub fn decode_stream_with<R: AsyncRead>(input: R, mut codec: SSECodec) -> DecodeStream<R> {
+    codec.reset_input();
+    FramedRead::new(input, codec)
+}
+
+/// Parse messages from an `AsyncRead`, returning a stream of `BytesEvent`s.
+///
+/// Unlike the `futures_codec` version, the input is copied once into the decoder's own buffer.
+pub fn decode_bytes_stream<R: AsyncRead>(input: R) -> BytesDecodeStream<R> {
+    decode_bytes_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from an `AsyncRead` using a configured codec, returning a stream of
+/// `BytesEvent`s.
+pub fn decode_bytes_stream_with<R: AsyncRead>(input: R, codec: SSECodec) -> BytesDecodeStream<R> {
+    FramedRead::new(input, BytesDecoder::new(codec))
+}
+
+/// Encode `Event`s into an `AsyncWrite`.
+pub fn encode_stream<W: AsyncWrite>(output: W) -> EncodeStream<W> {
+    encode_stream_with(output, SSECodec::default())
+}
+
+/// Encode `Event`s into an `AsyncWrite` using a configured codec.
+pub fn encode_stream_with<W: AsyncWrite>(output: W, codec: SSECodec) -> EncodeStream<W> {
+    FramedWrite::new(output, codec)
+}
+
+/// Encode `Frame`s into an `AsyncWrite`.
+pub fn encode_frame_stream<W: AsyncWrite>(output: W) -> FrameEncodeStream<W> {
+    FramedWrite::new(output, FrameEncoder::default())
+}
+
+/// Encode `Event`s into an `AsyncWrite`, assigning IDs to messages that do not have one.
+pub fn encode_auto_id_stream<W: AsyncWrite, G: IdGenerator>(
+    output: W,
+    encoder: AutoId<G>,
+) -> AutoIdEncodeStream<W, G> {
+    FramedWrite::new(output, encoder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Violation;
+    use futures::{SinkExt, StreamExt};
+
+    #[async_std::test]
+    async fn decode() {
+        let input: &[u8] = b"retry: 10\nid: 1\ndata: a\ndata: b\n\ndata: trailing";
+        let mut events = decode_stream(input);
+        assert_eq!(events.next().await.unwrap().unwrap(), Event::retry(10));
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            Event::message("message", "a\nb", "1")
+        );
+        assert!(events.next().await.is_none());
+        assert_eq!(events.last_event_id(), Some("1"));
+        assert_eq!(events.reconnection_time(), Some(10));
+    }
+
+    #[async_std::test]
+    async fn decode_bytes() {
+        let input: &[u8] = b"event: add\ndata: 1\n\n";
+        let mut events = decode_bytes_stream(input);
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            BytesEvent::from(Event::message("add", "1", None))
+        );
+        assert!(events.next().await.is_none());
+    }
+
+    #[async_std::test]
+    async fn trailing_data() {
+        let input: &[u8] = b"data: 1\n\ndata: trailing";
+        let codec = SSECodec::new().with_validation(true);
+        let mut events = decode_stream_with(input, codec);
+        assert!(events.next().await.unwrap().is_ok());
+        assert!(matches!(
+            events.next().await.unwrap(),
+            Err(Error::SpecViolation(Violation::TrailingData, _))
+        ));
+    }
+
+    #[async_std::test]
+    async fn encode() {
+        let mut output = vec![];
+        let mut stream = encode_stream(&mut output);
+        stream
+            .send(Event::message("add", "a\nb", "1"))
+            .await
+            .unwrap();
+        stream.send(Event::retry(100)).await.unwrap();
+        drop(stream);
+        assert_eq!(
+            output,
+            b"id: 1\nevent: add\ndata: a\ndata: b\n\nretry: 100\n\n".to_vec()
+        );
+    }
+}
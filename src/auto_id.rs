@@ -0,0 +1,305 @@
+use crate::{EncodeBuf, Error, Event, SSECodec};
+#[cfg(feature = "futures_codec")]
+use bytes::BytesMut;
+#[cfg(feature = "futures_codec")]
+use futures_codec::Encoder;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of event IDs for [`AutoId`].
+///
+/// Generators produce the next ID from the previous one, so an [`AutoId`] encoder can continue
+/// after a server restart with [`AutoId::with_last_id`]. This is implemented for functions and
+/// closures that take the last ID and return the next one.
+pub trait IdGenerator {
+    /// Generate the ID for the next event. `last_id` is the ID of the previous event, if there is
+    /// one.
+    fn next_id(&mut self, last_id: Option<&str>) -> String;
+}
+
+impl<F> IdGenerator for F
+where
+    F: FnMut(Option<&str>) -> String,
+{
+    fn next_id(&mut self, last_id: Option<&str>) -> String {
+        self(last_id)
+    }
+}
+
+/// Generates consecutive numbers, starting at 1.
+///
+/// If the last ID is not a number, counting starts over at 1.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counter;
+
+impl IdGenerator for Counter {
+    fn next_id(&mut self, last_id: Option<&str>) -> String {
+        let last = last_id.and_then(|id| id.parse::<u64>().ok()).unwrap_or(0);
+        last.saturating_add(1).to_string()
+    }
+}
+
+/// Generates the current time in milliseconds since the Unix epoch.
+///
+/// If several events are encoded in the same millisecond, or the clock goes backwards, the ID is
+/// one more than the last ID instead, so IDs always increase.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timestamp;
+
+impl IdGenerator for Timestamp {
+    fn next_id(&mut self, last_id: Option<&str>) -> String {
+        let now = now_millis();
+        match last_id.and_then(|id| id.parse::<u64>().ok()) {
+            Some(last) if last >= now => last.saturating_add(1).to_string(),
+            _ => now.to_string(),
+        }
+    }
+}
+
+/// Generates [ULID](https://github.com/ulid/spec)-like IDs: 26 characters that contain a
+/// millisecond timestamp followed by random bits, and sort in the order they were generated.
+///
+/// Like monotonic ULIDs, IDs that are generated in the same millisecond as the last ID increment
+/// it instead of using new random bits. The random bits come from the standard library's hasher
+/// keys, so they are unique but not suitable for security purposes.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ulid;
+
+/// The alphabet of Crockford's Base32, used by ULIDs.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The number of random bits in a ULID.
+const ULID_RANDOM_BITS: u32 = 80;
+
+impl Ulid {
+    fn encode(value: u128) -> String {
+        (0..26)
+            .rev()
+            .map(|index| CROCKFORD[((value >> (index * 5)) & 0x1f) as usize] as char)
+            .collect()
+    }
+
+    fn decode(id: &str) -> Option<u128> {
+        if id.len() != 26 {
+            return None;
+        }
+        id.bytes().try_fold(0u128, |value, byte| {
+            let digit = CROCKFORD.iter().position(|c| *c == byte)?;
+            value.checked_mul(32)?.checked_add(digit as u128)
+        })
+    }
+
+    fn random() -> u128 {
+        let high = RandomState::new().build_hasher().finish();
+        let low = RandomState::new().build_hasher().finish();
+        ((u128::from(high) << 64) | u128::from(low)) & ((1 << ULID_RANDOM_BITS) - 1)
+    }
+}
+
+impl IdGenerator for Ulid {
+    fn next_id(&mut self, last_id: Option<&str>) -> String {
+        let now = u128::from(now_millis()) & ((1 << 48) - 1);
+        let value = match last_id.and_then(Ulid::decode) {
+            Some(last) if last >> ULID_RANDOM_BITS >= now => last.wrapping_add(1),
+            _ => (now << ULID_RANDOM_BITS) | Ulid::random(),
+        };
+        Ulid::encode(value)
+    }
+}
+
+/// The current time in milliseconds since the Unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64)
+}
+
+/// Encoder that assigns IDs to messages that do not have one.
+///
+/// Clients send the ID of the last event they received in the `Last-Event-ID` header when they
+/// reconnect, so the server can resume the stream from there. Messages that already have an ID
+/// keep it, without affecting the IDs that are generated; retry events and comments never get
+/// one.
+///
+/// The encoder can be accessed while encoding using `FramedWrite::encoder()`, for example to read
+/// the last ID.
+///
+/// # Examples
+#[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+#[cfg_attr(feature = "futures_codec", doc = "```rust")]
+/// use sse_codec::{encode_auto_id_stream, AutoId, Counter, Event};
+/// use futures::sink::SinkExt;
+///
+/// # async_std::task::block_on(async {
+/// let mut output = vec![];
+/// // Continue after the last ID that was sent before a restart.
+/// let encoder = AutoId::new(Counter).with_last_id("41");
+/// let mut events = encode_auto_id_stream(&mut output, encoder);
+/// events.send(Event::message("message", "hello", None)).await?;
+/// assert_eq!(events.encoder().last_id(), Some("42"));
+/// drop(events);
+/// assert_eq!(output, b"id: 42\ndata: hello\n\n");
+/// # Ok::<(), sse_codec::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutoId<G> {
+    codec: SSECodec,
+    generator: G,
+    /// The last ID that was generated, which the next ID is generated from.
+    last_generated: Option<String>,
+    /// The ID of the last message that was written.
+    last_id: Option<String>,
+}
+
+impl<G: IdGenerator> AutoId<G> {
+    /// Create an encoder that assigns IDs from `generator`.
+    pub fn new(generator: G) -> Self {
+        Self {
+            codec: SSECodec::default(),
+            generator,
+            last_generated: None,
+            last_id: None,
+        }
+    }
+
+    /// Encode events with the configuration from `codec`.
+    pub fn with_codec(mut self, codec: SSECodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Continue after `id`, for example the last ID that was sent before a server restart. The
+    /// next ID is generated from it.
+    pub fn with_last_id(mut self, id: &str) -> Self {
+        self.last_generated = Some(id.to_string());
+        self.last_id = Some(id.to_string());
+        self
+    }
+
+    /// Get the underlying codec.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+
+    /// The ID of the last message that was encoded, whether it was assigned or already set.
+    ///
+    /// This is the ID as it was written, after the codec's [`EncodePolicy`](crate::EncodePolicy)
+    /// was applied, so it is what clients send back in the `Last-Event-ID` header.
+    pub fn last_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// Assign an ID if needed, and encode the event.
+    pub(crate) fn encode_event(
+        &mut self,
+        mut item: Event,
+        dest: &mut impl EncodeBuf,
+    ) -> Result<(), Error> {
+        let mut generated = None;
+        if let Event::Message { id: id @ None, .. } = &mut item {
+            let next = self.generator.next_id(self.last_generated.as_deref());
+            *id = Some(next.clone());
+            generated = Some(next);
+        }
+        let item = item.apply_policy(self.codec.encode_policy())?;
+        self.codec.write_event(&item, dest);
+        // Only update the last IDs once the event was encoded, so a rejected event does not use up
+        // an ID. IDs that were already set are not passed to the generator, because it may not
+        // understand them.
+        if let Event::Message { id: Some(id), .. } = item {
+            self.last_id = Some(id);
+        }
+        if generated.is_some() {
+            self.last_generated = generated;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures_codec")]
+impl<G: IdGenerator> Encoder for AutoId<G> {
+    type Item = Event;
+    type Error = Error;
+
+    fn encode(&mut self, item: Self::Item, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_event(item, dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    #[test]
+    fn counter() {
+        assert_eq!(Counter.next_id(None), "1");
+        assert_eq!(Counter.next_id(Some("41")), "42");
+        assert_eq!(Counter.next_id(Some("abc")), "1");
+    }
+
+    #[test]
+    fn timestamp() {
+        let first = Timestamp.next_id(None);
+        assert!(first.parse::<u64>().unwrap() > 1_500_000_000_000);
+        let future = (now_millis() + 60_000).to_string();
+        let next = Timestamp.next_id(Some(&future));
+        assert_eq!(next.parse::<u64>().unwrap(), now_millis() + 60_000 + 1);
+    }
+
+    #[test]
+    fn ulid() {
+        let first = Ulid.next_id(None);
+        assert_eq!(first.len(), 26);
+        assert_eq!(Ulid::encode(Ulid::decode(&first).unwrap()), first);
+        let second = Ulid.next_id(Some(&first));
+        assert!(second > first);
+        assert_ne!(Ulid.next_id(None), Ulid.next_id(None));
+
+        // Within the same millisecond, the last ID is incremented.
+        let future = Ulid::encode((u128::from(now_millis() + 60_000) << 80) | 0x1e);
+        let next = Ulid.next_id(Some(&future));
+        assert_eq!(&next[..25], &future[..25]);
+        assert_eq!(&next[25..], "Z");
+    }
+
+    #[test]
+    fn assign_ids() {
+        let mut encoder = AutoId::new(Counter);
+        let mut output = BytesMut::new();
+        let events = vec![
+            Event::message("message", "a", None),
+            Event::retry(10),
+            Event::message("message", "b", "custom"),
+            Event::message("message", "c", None),
+            Event::message("bad\nevent", "d", None),
+            Event::message("message", "e", None),
+        ];
+        for event in events {
+            let _ = encoder.encode_event(event, &mut output);
+        }
+        assert_eq!(
+            output,
+            &b"id: 1\ndata: a\n\nretry: 10\n\nid: custom\ndata: b\n\nid: 2\ndata: c\n\nid: 3\ndata: e\n\n"[..]
+        );
+        assert_eq!(encoder.last_id(), Some("3"));
+    }
+
+    #[test]
+    fn last_id_is_written_id() {
+        let codec = SSECodec::new().with_encode_policy(crate::EncodePolicy::Strip);
+        let mut encoder = AutoId::new(Counter).with_codec(codec);
+        let mut output = BytesMut::new();
+        encoder
+            .encode_event(Event::message("message", "a", "7\n"), &mut output)
+            .unwrap();
+        assert_eq!(encoder.last_id(), Some("7"));
+        encoder
+            .encode_event(Event::message("message", "b", None), &mut output)
+            .unwrap();
+        assert_eq!(output, &b"id: 7\ndata: a\n\nid: 1\ndata: b\n\n"[..]);
+        assert_eq!(encoder.last_id(), Some("1"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Blocking encoding and decoding over `std::io::Read` and `std::io::Write`.
+//!
+//! These use the same [`SSECodec`] as the async streams, without needing an executor.
+//!
+//! # Examples
+//! ```rust
+//! use sse_codec::{blocking, Event};
+//!
+//! # fn main() -> Result<(), sse_codec::Error> {
+//! let mut output = vec![];
+//! let mut writer = blocking::encode_writer(&mut output);
+//! writer.send(Event::message("add", "1", None))?;
+//!
+//! let events = blocking::decode_iter(&output[..]).collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(events, vec![Event::message("add", "1", None)]);
+//! # Ok(()) }
+//! ```
+use crate::{AutoId, Error, Event, Frame, IdGenerator, SSECodec, StreamState};
+use bytes::BytesMut;
+use std::io::{self, Read, Write};
+
+/// How many bytes to read from the input at a time.
+const READ_SIZE: usize = 8 * 1024;
+
+/// An iterator over the `Event`s in a `Read`, returned from `decode_iter()`.
+#[derive(Debug)]
+pub struct DecodeIter<R> {
+    reader: R,
+    codec: SSECodec,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl<R: Read> DecodeIter<R> {
+    /// Get the underlying codec.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume the iterator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read more input into the buffer. Returns the number of bytes read.
+    fn fill_buffer(&mut self) -> io::Result<usize> {
+        let mut chunk = [0; READ_SIZE];
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(n) => {
+                    self.buffer.extend_from_slice(&chunk[..n]);
+                    return Ok(n);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for DecodeIter<R> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match self.codec.decode_parsed(&mut self.buffer) {
+                Ok(Some(parsed)) => return Some(Ok(parsed.into_event())),
+                Ok(None) => (),
+                Err(err) => return Some(Err(err)),
+            }
+            match self.fill_buffer() {
+                Ok(0) => {
+                    self.done = true;
+                    return self.codec.discard_trailing(&mut self.buffer).err().map(Err);
+                }
+                Ok(_) => (),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+impl<R: Read> StreamState for DecodeIter<R> {
+    fn last_event_id(&self) -> Option<&str> {
+        self.codec.last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.codec.reconnection_time()
+    }
+}
+
+mod sealed {
+    use super::*;
+
+    /// An encoder that can be used by [`EncodeWriter`]: [`SSECodec`] or [`AutoId`].
+    pub trait EventEncoder {
+        /// Encode an event into `dest`.
+        fn encode_event(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Error>;
+    }
+
+    impl EventEncoder for SSECodec {
+        fn encode_event(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Error> {
+            SSECodec::encode_event(self, item, dest)
+        }
+    }
+
+    impl<G: IdGenerator> EventEncoder for AutoId<G> {
+        fn encode_event(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Error> {
+            AutoId::encode_event(self, item, dest)
+        }
+    }
+}
+
+use sealed::EventEncoder;
+
+/// Writes `Event`s to a `Write`, returned from `encode_writer()` and `encode_auto_id_writer()`.
+///
+/// Every event is written to the underlying writer as soon as it is sent. Wrap the writer in a
+/// `std::io::BufWriter` to reduce the number of writes.
+#[derive(Debug)]
+pub struct EncodeWriter<W, E = SSECodec> {
+    writer: W,
+    codec: E,
+    buffer: BytesMut,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    /// Encode a frame and write it.
+    pub fn send_frame(&mut self, frame: Frame) -> Result<(), Error> {
+        self.codec.encode_frame(frame, &mut self.buffer)?;
+        self.write_buffer()
+    }
+}
+
+impl<W: Write, E: EventEncoder> EncodeWriter<W, E> {
+    /// Encode an event and write it.
+    pub fn send(&mut self, event: Event) -> Result<(), Error> {
+        self.codec.encode_event(event, &mut self.buffer)?;
+        self.write_buffer()
+    }
+
+    /// Get the underlying encoder.
+    pub fn encoder(&self) -> &E {
+        &self.codec
+    }
+
+    /// Write the encoded buffer to the underlying writer.
+    fn write_buffer(&mut self) -> Result<(), Error> {
+        let result = self.writer.write_all(&self.buffer);
+        self.buffer.clear();
+        result.map_err(Into::into)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Into::into)
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consume the encoder, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Parse messages from a `Read`, returning an iterator of `Event`s.
+pub fn decode_iter<R: Read>(input: R) -> DecodeIter<R> {
+    decode_iter_with(input, SSECodec::default())
+}
+
+/// Parse messages from a `Read` using a configured codec, returning an iterator of `Event`s.
+pub fn decode_iter_with<R: Read>(input: R, mut codec: SSECodec) -> DecodeIter<R> {
+    codec.reset_input();
+    DecodeIter {
+        reader: input,
+        codec,
+        buffer: BytesMut::new(),
+        done: false,
+    }
+}
+
+/// Encode `Event`s into a `Write`.
+pub fn encode_writer<W: Write>(output: W) -> EncodeWriter<W> {
+    encode_writer_with(output, SSECodec::default())
+}
+
+/// Encode `Event`s into a `Write` using a configured codec.
+pub fn encode_writer_with<W: Write>(output: W, codec: SSECodec) -> EncodeWriter<W> {
+    EncodeWriter {
+        writer: output,
+        codec,
+        buffer: BytesMut::new(),
+    }
+}
+
+/// Encode `Event`s into a `Write`, assigning IDs to messages that do not have one.
+pub fn encode_auto_id_writer<W: Write, G: IdGenerator>(
+    output: W,
+    encoder: AutoId<G>,
+) -> EncodeWriter<W, AutoId<G>> {
+    EncodeWriter {
+        writer: output,
+        codec: encoder,
+        buffer: BytesMut::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limits, Utf8Mode, Violation};
+
+    /// A reader that returns its input one byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((byte, rest)) if !buf.is_empty() => {
+                    buf[0] = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let input = Trickle(b"retry:10\nid:1\ndata:a\ndata:b\n\ndata:c\n\ndata:trailing");
+        let mut events = decode_iter(input);
+        assert_eq!(events.next().unwrap().unwrap(), Event::retry(10));
+        assert_eq!(
+            events.next().unwrap().unwrap(),
+            Event::message("message", "a\nb", "1")
+        );
+        assert_eq!(
+            events.next().unwrap().unwrap(),
+            Event::message("message", "c", "1")
+        );
+        assert!(events.next().is_none());
+        assert!(events.next().is_none());
+        assert_eq!(events.last_event_id(), Some("1"));
+        assert_eq!(events.reconnection_time(), Some(10));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let codec = SSECodec::new()
+            .with_utf8_mode(Utf8Mode::Lossy)
+            .with_limits(Limits::new().max_line_length(6));
+        let input: &[u8] = b"data:\xFF\n\ndata:1234\n\ndata:2\n\n";
+        let results: Vec<_> = decode_iter_with(input, codec).collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &Event::message("message", "\u{fffd}", None)
+        );
+        assert!(matches!(results[1], Err(Error::LineTooLong(_))));
+        assert_eq!(
+            results[2].as_ref().unwrap(),
+            &Event::message("message", "2", None)
+        );
+    }
+
+    #[test]
+    fn decode_trailing_data() {
+        let codec = SSECodec::new().with_validation(true);
+        let input: &[u8] = b"data: 1\n\ndata: 2\n";
+        let mut events = decode_iter_with(input, codec);
+        assert!(events.next().unwrap().is_ok());
+        assert!(matches!(
+            events.next(),
+            Some(Err(Error::SpecViolation(Violation::TrailingData, _)))
+        ));
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn encode_auto_id() {
+        let encoder = AutoId::new(crate::Counter).with_last_id("9");
+        let mut writer = encode_auto_id_writer(vec![], encoder);
+        writer.send(Event::message("add", "1", None)).unwrap();
+        assert_eq!(writer.encoder().last_id(), Some("10"));
+        assert_eq!(
+            writer.into_inner(),
+            b"id: 10\nevent: add\ndata: 1\n\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn encode() {
+        let mut writer = encode_writer(vec![]);
+        writer.send(Event::message("add", "a\nb", "1")).unwrap();
+        writer.send(Event::retry(100)).unwrap();
+        writer.send_frame(Frame::new().retry(5).id("")).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            b"id: 1\nevent: add\ndata: a\ndata: b\n\nretry: 100\n\nretry: 5\nid\n\n".to_vec()
+        );
+    }
+}
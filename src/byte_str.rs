@@ -0,0 +1,168 @@
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use bytes::Bytes;
+use core::borrow::Borrow;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, RangeBounds};
+use core::{fmt, str::Utf8Error};
+
+/// Decode a byte slice as UTF-8, replacing invalid sequences with U+FFFD REPLACEMENT CHARACTER.
+///
+/// This behaves like `String::from_utf8_lossy`, but also returns the number of replacements that
+/// were made.
+pub(crate) fn decode_utf8_lossy(mut input: &[u8]) -> (Cow<'_, str>, usize) {
+    let mut output = match core::str::from_utf8(input) {
+        Ok(valid) => return (Cow::Borrowed(valid), 0),
+        Err(_) => String::with_capacity(input.len()),
+    };
+    let mut replacements = 0;
+    loop {
+        match core::str::from_utf8(input) {
+            Ok(valid) => {
+                output.push_str(valid);
+                break;
+            }
+            Err(err) => {
+                let (valid, rest) = input.split_at(err.valid_up_to());
+                // Safety: `valid_up_to()` tells us the prefix is valid utf-8.
+                output.push_str(unsafe { core::str::from_utf8_unchecked(valid) });
+                output.push('\u{fffd}');
+                replacements += 1;
+                match err.error_len() {
+                    Some(len) => input = &rest[len..],
+                    // The input ended in the middle of a sequence.
+                    None => break,
+                }
+            }
+        }
+    }
+    (Cow::Owned(output), replacements)
+}
+
+/// An immutable UTF-8 string backed by reference-counted [`Bytes`].
+///
+/// Cloning and slicing a `ByteStr` does not copy the string data. It dereferences to `str`, so it
+/// can be used like a `&str`.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteStr(Bytes);
+
+impl ByteStr {
+    /// Create a `ByteStr` from a static string, without copying.
+    pub const fn from_static(string: &'static str) -> Self {
+        ByteStr(Bytes::from_static(string.as_bytes()))
+    }
+
+    /// Create a `ByteStr` from bytes containing valid UTF-8, without copying.
+    pub fn from_utf8(bytes: Bytes) -> Result<Self, Utf8Error> {
+        core::str::from_utf8(&bytes)?;
+        Ok(ByteStr(bytes))
+    }
+
+    /// Create a `ByteStr` from bytes, replacing invalid UTF-8 sequences with U+FFFD REPLACEMENT
+    /// CHARACTER. Only copies if there are invalid sequences.
+    ///
+    /// Returns the string and the number of replacements that were made.
+    pub(crate) fn from_utf8_lossy(bytes: Bytes) -> (Self, usize) {
+        match decode_utf8_lossy(&bytes) {
+            (Cow::Owned(string), replacements) => (ByteStr::from(string), replacements),
+            (Cow::Borrowed(_), _) => (ByteStr(bytes.clone()), 0),
+        }
+    }
+
+    /// Extract a string slice containing the entire `ByteStr`.
+    pub fn as_str(&self) -> &str {
+        // Safety: all constructors check that the contents are valid utf-8.
+        unsafe { core::str::from_utf8_unchecked(&self.0) }
+    }
+
+    /// Get the underlying bytes.
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+
+    /// Take a slice of this string without copying. The range must be on `char` boundaries.
+    pub(crate) fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        ByteStr(self.0.slice(range))
+    }
+}
+
+impl Deref for ByteStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ByteStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for ByteStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Hash for ByteStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must hash the same way as `str` for the `Borrow<str>` impl.
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for ByteStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ByteStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl From<String> for ByteStr {
+    fn from(string: String) -> Self {
+        ByteStr(Bytes::from(string))
+    }
+}
+
+impl From<&str> for ByteStr {
+    fn from(string: &str) -> Self {
+        ByteStr(Bytes::copy_from_slice(string.as_bytes()))
+    }
+}
+
+impl From<ByteStr> for String {
+    fn from(string: ByteStr) -> Self {
+        string.as_str().to_string()
+    }
+}
+
+impl From<ByteStr> for Bytes {
+    fn from(string: ByteStr) -> Self {
+        string.0
+    }
+}
+
+impl PartialEq<str> for ByteStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ByteStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for ByteStr {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
@@ -0,0 +1,364 @@
+use crate::{
+    check_custom_field, check_value, display_parts, encode_comment, encode_data, encode_field,
+    encode_retry, Error, Event, SSECodec, ID_FORBIDDEN, LINE_BREAKS,
+};
+#[cfg(feature = "std")]
+use crate::{encode_custom_field, encode_value, EncodeBuf, EncodePolicy};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "futures_codec")]
+use bytes::BytesMut;
+use core::fmt;
+#[cfg(feature = "futures_codec")]
+use futures_codec::Encoder;
+
+/// A single field in a [`Frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// An `id:` field. An empty ID resets the _last event ID_.
+    Id(String),
+    /// An `event:` field.
+    Event(String),
+    /// The data for the event. Each line is written as a separate `data:` field.
+    Data(String),
+    /// A `retry:` field, setting the _reconnection time_ in milliseconds.
+    Retry(u64),
+    /// A comment. Each line is written as a separate comment line.
+    Comment(String),
+    /// A custom field that is not part of the spec, as a name/value pair.
+    Custom(String, String),
+}
+
+/// An outgoing frame, consisting of any combination of fields in any order.
+///
+/// Unlike [`Event`], a frame can combine `retry:`, `id:`, `event:` and `data:` fields, and an
+/// `id:` field without data. Frames are encoded by [`FrameEncoder`](crate::FrameEncoder), and
+/// every `Event` can be converted into a `Frame`.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::Frame;
+///
+/// let frame = Frame::new()
+///     .retry(3000)
+///     .id("42")
+///     .event("update")
+///     .data("line 1\nline 2");
+/// assert_eq!(
+///     frame.to_string(),
+///     "retry: 3000\nid: 42\nevent: update\ndata: line 1\ndata: line 2\n"
+/// );
+///
+/// // Reset the last event ID.
+/// assert_eq!(Frame::new().id("").to_string(), "id\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    fields: Vec<Field>,
+}
+
+impl Frame {
+    /// Create an empty frame.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field.
+    pub fn push(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Add an `id:` field. Pass an empty string to reset the _last event ID_.
+    pub fn id(self, id: &str) -> Self {
+        self.push(Field::Id(id.to_string()))
+    }
+
+    /// Add an `event:` field.
+    pub fn event(self, event: &str) -> Self {
+        self.push(Field::Event(event.to_string()))
+    }
+
+    /// Add data. Each line is written as a separate `data:` field.
+    pub fn data(self, data: &str) -> Self {
+        self.push(Field::Data(data.to_string()))
+    }
+
+    /// Add a `retry:` field.
+    pub fn retry(self, time: u64) -> Self {
+        self.push(Field::Retry(time))
+    }
+
+    /// Add a comment.
+    pub fn comment(self, comment: &str) -> Self {
+        self.push(Field::Comment(comment.to_string()))
+    }
+
+    /// Add a custom field.
+    pub fn field(self, name: &str, value: &str) -> Self {
+        self.push(Field::Custom(name.to_string(), value.to_string()))
+    }
+
+    /// The fields in this frame, in the order they are written.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Check that this frame can be encoded without changing its meaning.
+    ///
+    /// The same rules apply as for [`Event::validate`].
+    pub fn validate(&self) -> Result<(), Error> {
+        for field in &self.fields {
+            match field {
+                Field::Id(id) => check_value("id", id, ID_FORBIDDEN)?,
+                Field::Event(event) => check_value("event", event, LINE_BREAKS)?,
+                Field::Custom(name, value) => check_custom_field(name, value)?,
+                Field::Data(_) | Field::Comment(_) | Field::Retry(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Make this frame safe to encode according to `policy`.
+    #[cfg(feature = "std")]
+    pub(crate) fn apply_policy(self, policy: EncodePolicy) -> Result<Self, Error> {
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for field in self.fields {
+            let field = match field {
+                Field::Id(id) => Field::Id(encode_value("id", id, ID_FORBIDDEN, policy)?),
+                Field::Event(event) => {
+                    Field::Event(encode_value("event", event, LINE_BREAKS, policy)?)
+                }
+                Field::Custom(name, value) => match encode_custom_field(name, value, policy)? {
+                    Some((name, value)) => Field::Custom(name, value),
+                    None => continue,
+                },
+                field @ (Field::Data(_) | Field::Comment(_) | Field::Retry(_)) => field,
+            };
+            fields.push(field);
+        }
+        Ok(Frame { fields })
+    }
+}
+
+impl From<Event> for Frame {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => {
+                let mut frame = Frame::new();
+                if let Some(id) = id {
+                    frame = frame.push(Field::Id(id));
+                }
+                if event != crate::DEFAULT_EVENT_TYPE {
+                    frame = frame.push(Field::Event(event));
+                }
+                for (name, value) in fields {
+                    frame = frame.push(Field::Custom(name, value));
+                }
+                frame.push(Field::Data(data))
+            }
+            Event::Retry { retry } => Frame::new().retry(retry),
+            Event::Comment { comment } => Frame::new().push(Field::Comment(comment)),
+        }
+    }
+}
+
+impl Field {
+    /// Pass the encoded field to `out`, piece by piece.
+    fn encode_parts(&self, eol: &str, out: &mut impl FnMut(&str)) {
+        match self {
+            Field::Id(id) => encode_field(out, eol, "id", id),
+            Field::Event(event) => encode_field(out, eol, "event", event),
+            Field::Data(data) => encode_data(out, eol, data),
+            Field::Retry(retry) => encode_retry(out, eol, *retry),
+            Field::Comment(comment) => encode_comment(out, eol, comment),
+            Field::Custom(name, value) => encode_field(out, eol, name, value),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_parts(f, |mut out| self.encode_parts("\n", &mut out))
+    }
+}
+
+impl Frame {
+    /// The number of bytes the encoder writes for this frame, including the blank line that ends
+    /// it.
+    ///
+    /// See [`Event::encoded_len`].
+    pub fn encoded_len(&self) -> usize {
+        self.encoded_len_with("\n")
+    }
+
+    /// The number of bytes the encoder writes for this frame with `eol` as the line terminator.
+    fn encoded_len_with(&self, eol: &str) -> usize {
+        let mut len = eol.len();
+        self.encode_parts(eol, &mut |part| len += part.len());
+        len
+    }
+
+    /// Write the encoded frame, including the blank line that ends it, reserving the exact size up
+    /// front.
+    #[cfg(feature = "std")]
+    pub(crate) fn encode_into(&self, eol: &str, dest: &mut impl EncodeBuf) {
+        dest.reserve(self.encoded_len_with(eol));
+        self.encode_parts(eol, &mut |part| dest.put(part.as_bytes()));
+        dest.put(eol.as_bytes());
+    }
+
+    /// Pass the encoded frame to `out`, piece by piece, without the blank line that ends it.
+    fn encode_parts(&self, eol: &str, out: &mut impl FnMut(&str)) {
+        for field in &self.fields {
+            field.encode_parts(eol, out);
+        }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_parts(f, |mut out| self.encode_parts("\n", &mut out))
+    }
+}
+
+/// Encoder for [`Frame`]s.
+///
+/// Frames are checked against the codec's [`EncodePolicy`](crate::EncodePolicy) before they are
+/// written, just like events.
+#[derive(Debug, Default, Clone)]
+pub struct FrameEncoder {
+    pub(crate) codec: SSECodec,
+}
+
+impl FrameEncoder {
+    /// Create an encoder with the configuration from `codec`.
+    pub fn new(codec: SSECodec) -> Self {
+        Self { codec }
+    }
+
+    /// Get the underlying codec.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+}
+
+impl From<SSECodec> for FrameEncoder {
+    fn from(codec: SSECodec) -> Self {
+        Self::new(codec)
+    }
+}
+
+#[cfg(feature = "futures_codec")]
+impl Encoder for FrameEncoder {
+    type Item = Frame;
+    type Error = Error;
+
+    fn encode(&mut self, item: Self::Item, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.codec.encode_frame(item, dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, SSECodec};
+    use alloc::vec;
+
+    #[test]
+    fn field_order() {
+        let frame = Frame::new()
+            .comment("hello")
+            .data("a")
+            .field("trace", "1")
+            .event("add")
+            .data("")
+            .id("1")
+            .retry(10);
+        assert_eq!(
+            frame.to_string(),
+            ": hello\ndata: a\ntrace: 1\nevent: add\ndata\nid: 1\nretry: 10\n"
+        );
+        assert_eq!(frame.encoded_len(), frame.to_string().len() + 1);
+    }
+
+    #[test]
+    fn from_event() {
+        let events = vec![
+            Event::message("add", "a\nb", "1"),
+            Event::message("message", "", None),
+            Event::retry(10),
+            Event::comment("keep-alive"),
+        ];
+        for event in events {
+            assert_eq!(Frame::from(event.clone()).to_string(), event.to_string());
+        }
+    }
+
+    #[test]
+    fn decode_combined_frame() {
+        let frame = Frame::new().retry(10).id("1").event("add").data("x");
+        let mut parser = Parser::with_codec(SSECodec::new());
+        parser.feed(frame.to_string().as_bytes());
+        parser.feed(b"\n");
+        assert_eq!(parser.next_event().unwrap(), Some(Event::retry(10)));
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(Event::message("add", "x", "1"))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn policy() {
+        let frame = Frame::new()
+            .id("1\n")
+            .event("a\rb")
+            .field("data", "x")
+            .data("c\r\nd")
+            .comment("e\nf");
+        assert!(matches!(
+            frame.validate(),
+            Err(Error::InvalidField(field)) if field == "id"
+        ));
+        Frame::new()
+            .data("c\r\nd")
+            .comment("e\nf")
+            .validate()
+            .unwrap();
+        assert_eq!(
+            frame.apply_policy(EncodePolicy::Strip).unwrap(),
+            Frame::new()
+                .id("1")
+                .event("ab")
+                .data("c\r\nd")
+                .comment("e\nf")
+        );
+    }
+
+    #[cfg(feature = "futures_codec")]
+    #[async_std::test]
+    async fn encode() {
+        use crate::{encode_frame_stream, FrameEncodeStream};
+        use futures::SinkExt;
+
+        let mut output = vec![];
+        let mut stream = encode_frame_stream(&mut output);
+        stream.send(Frame::new().id("")).await.unwrap();
+        stream.send(Event::retry(10).into()).await.unwrap();
+        drop(stream);
+        assert_eq!(output, b"id\n\nretry: 10\n\n".to_vec());
+
+        let encoder = FrameEncoder::new(SSECodec::new());
+        let mut stream = FrameEncodeStream::new(vec![], encoder);
+        assert!(matches!(
+            stream.send(Frame::new().event("a\nb")).await,
+            Err(Error::InvalidField(_))
+        ));
+    }
+}
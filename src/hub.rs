@@ -0,0 +1,400 @@
+use crate::{Error, Event, SSECodec};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use futures_core::Stream;
+use std::sync::{Mutex, MutexGuard};
+
+/// What a [`Hub`] does when a subscriber's queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlowConsumerPolicy {
+    /// Remove the oldest queued event to make room for the new one. This is the default.
+    #[default]
+    DropOldest,
+    /// Leave out the new event.
+    DropNewest,
+    /// End the subscriber's stream, discarding the queued events. The client can reconnect and
+    /// resume from its last event ID.
+    Disconnect,
+}
+
+/// The events queued for a single subscriber.
+#[derive(Debug, Default)]
+struct Queue {
+    /// The events are shared between all queues they were published to.
+    events: VecDeque<Arc<Event>>,
+    /// The number of events that were left out because the queue was full.
+    dropped: u64,
+    /// Has the subscriber been disconnected, or the hub been dropped?
+    closed: bool,
+    /// The task waiting for the next event.
+    waker: Option<Waker>,
+}
+
+impl Queue {
+    fn close(&mut self) {
+        self.closed = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Lock a queue, ignoring poisoning: a queue is never left in an inconsistent state.
+fn lock(queue: &Mutex<Queue>) -> MutexGuard<'_, Queue> {
+    queue.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// The state that is shared by all clones of a hub. The queues of the subscribers are closed when
+/// the last clone is dropped.
+#[derive(Debug)]
+struct Shared {
+    queues: Vec<Arc<Mutex<Queue>>>,
+    capacity: usize,
+    policy: SlowConsumerPolicy,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        for queue in &self.queues {
+            lock(queue).close();
+        }
+    }
+}
+
+/// Broadcasts events to many subscribers, such as the clients of an event stream endpoint.
+///
+/// Every subscriber has its own bounded queue, so a slow client does not hold up the others.
+/// When a queue is full, the [`SlowConsumerPolicy`] decides what happens. The hub can be cloned
+/// to publish from several tasks; subscriber streams end when every clone has been dropped.
+///
+/// The hub does not depend on any async runtime or IO, so it can be tested in memory.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{Event, Hub, SSECodec};
+/// use futures::stream::StreamExt;
+///
+/// # async_std::task::block_on(async {
+/// let hub = Hub::new(16);
+/// let mut events = hub.subscribe();
+/// let mut bytes = hub.subscribe().encoded(SSECodec::new());
+///
+/// hub.publish(Event::message("add", "1", None));
+/// assert_eq!(events.next().await, Some(Event::message("add", "1", None)));
+/// assert_eq!(bytes.next().await.unwrap()?, b"event: add\ndata: 1\n\n");
+/// # Ok::<(), sse_codec::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Hub {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Hub {
+    /// Create a hub that queues up to `capacity` events for each subscriber.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "hub capacity must be at least 1");
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                queues: Vec::new(),
+                capacity,
+                policy: SlowConsumerPolicy::default(),
+            })),
+        }
+    }
+
+    /// Set what happens when a subscriber's queue is full. Defaults to
+    /// [`SlowConsumerPolicy::DropOldest`].
+    ///
+    /// The policy is shared by all clones of the hub.
+    pub fn set_policy(&self, policy: SlowConsumerPolicy) {
+        self.lock().policy = policy;
+    }
+
+    /// The maximum number of events queued for each subscriber.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    /// What happens when a subscriber's queue is full.
+    pub fn policy(&self) -> SlowConsumerPolicy {
+        self.lock().policy
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Add a subscriber that receives every event published from now on.
+    pub fn subscribe(&self) -> Subscriber {
+        let queue = Arc::new(Mutex::new(Queue::default()));
+        self.lock().queues.push(Arc::clone(&queue));
+        Subscriber { queue }
+    }
+
+    /// The number of connected subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        let mut shared = self.lock();
+        shared.queues.retain(|queue| !lock(queue).closed);
+        shared.queues.len()
+    }
+
+    /// Send an event to every subscriber. Returns the number of subscribers it was queued for.
+    ///
+    /// The event is not copied for every subscriber. A [`Subscriber`] only copies it when taking
+    /// it out while other subscribers still hold it, and an [`EncodedSubscriber`] encodes it in
+    /// place.
+    pub fn publish(&self, event: Event) -> usize {
+        let event = Arc::new(event);
+        let mut shared = self.lock();
+        let Shared {
+            queues,
+            capacity,
+            policy,
+        } = &mut *shared;
+        let mut queued = 0;
+        queues.retain(|queue| {
+            let mut queue = lock(queue);
+            if queue.closed {
+                return false;
+            }
+            if queue.events.len() >= *capacity {
+                match policy {
+                    SlowConsumerPolicy::DropOldest => {
+                        queue.events.pop_front();
+                        queue.dropped += 1;
+                    }
+                    SlowConsumerPolicy::DropNewest => {
+                        queue.dropped += 1;
+                        return true;
+                    }
+                    SlowConsumerPolicy::Disconnect => {
+                        queue.events.clear();
+                        queue.close();
+                        return false;
+                    }
+                }
+            }
+            queue.events.push_back(Arc::clone(&event));
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+            queued += 1;
+            true
+        });
+        queued
+    }
+}
+
+/// A stream of the events published to a [`Hub`], returned from [`Hub::subscribe`].
+///
+/// It can be sent into an [`EncodeStream`](crate::EncodeStream), or turned into a stream of
+/// encoded bytes with [`Subscriber::encoded`]. The stream ends when the subscriber is
+/// disconnected as a slow consumer, or when the hub is dropped.
+#[derive(Debug)]
+pub struct Subscriber {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Subscriber {
+    /// The number of events that were left out or removed because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        lock(&self.queue).dropped
+    }
+
+    /// Whether the stream has ended. Queued events can still be taken out of the stream.
+    pub fn is_closed(&self) -> bool {
+        lock(&self.queue).closed
+    }
+
+    /// Encode the events with `codec`, returning a stream of bytes that can be written to the
+    /// client.
+    pub fn encoded(self, codec: SSECodec) -> EncodedSubscriber {
+        EncodedSubscriber {
+            subscriber: self,
+            codec,
+        }
+    }
+
+    /// Take the next event out of the queue, without copying it.
+    fn poll_shared(&self, cx: &mut Context<'_>) -> Poll<Option<Arc<Event>>> {
+        let mut queue = lock(&self.queue);
+        if let Some(event) = queue.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+        queue.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Stream for Subscriber {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_shared(cx)
+            .map(|event| event.map(Arc::unwrap_or_clone))
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        // Mark the queue as closed, so the hub stops sending to it, and free the queued events
+        // right away. The hub only removes the queue on the next publish.
+        let mut queue = lock(&self.queue);
+        queue.closed = true;
+        queue.events = VecDeque::new();
+    }
+}
+
+/// A stream of encoded events published to a [`Hub`], returned from [`Subscriber::encoded`].
+#[derive(Debug)]
+pub struct EncodedSubscriber {
+    subscriber: Subscriber,
+    codec: SSECodec,
+}
+
+impl EncodedSubscriber {
+    /// Get a reference to the underlying subscriber.
+    pub fn get_ref(&self) -> &Subscriber {
+        &self.subscriber
+    }
+
+    /// Get the codec that encodes the events.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+}
+
+impl Stream for EncodedSubscriber {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match this.subscriber.poll_shared(cx) {
+            Poll::Ready(Some(event)) => {
+                let mut bytes = Vec::new();
+                let result = this.codec.encode_shared_event(&event, &mut bytes);
+                Poll::Ready(Some(result.map(|()| bytes)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use futures::{FutureExt, StreamExt};
+
+    fn message(data: &str) -> Event {
+        Event::message("message", data, None)
+    }
+
+    /// Take every event that is currently queued for `subscriber`.
+    fn drain(subscriber: &mut Subscriber) -> Vec<Event> {
+        let mut events = vec![];
+        while let Some(Some(event)) = subscriber.next().now_or_never() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn broadcast() {
+        let hub = Hub::new(4);
+        let mut a = hub.subscribe();
+        let mut b = hub.subscribe();
+        assert_eq!(hub.publish(message("1")), 2);
+        let queue = Arc::clone(&b.queue);
+        drop(b);
+        assert!(lock(&queue).events.is_empty());
+        assert_eq!(hub.publish(message("2")), 1);
+        assert_eq!(hub.subscriber_count(), 1);
+        assert_eq!(drain(&mut a), vec![message("1"), message("2")]);
+        assert_eq!(a.next().now_or_never(), None);
+
+        b = hub.subscribe();
+        drop(hub);
+        assert_eq!(a.next().now_or_never(), Some(None));
+        assert_eq!(b.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    fn events_are_shared() {
+        let hub = Hub::new(4);
+        let a = hub.subscribe();
+        let b = hub.subscribe();
+        hub.publish(message("1"));
+        let event = Arc::clone(&lock(&a.queue).events[0]);
+        assert!(Arc::ptr_eq(&event, &lock(&b.queue).events[0]));
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let hub = Hub::new(2);
+        let mut subscriber = hub.subscribe();
+        for data in ["1", "2", "3"] {
+            hub.publish(message(data));
+        }
+        assert_eq!(drain(&mut subscriber), vec![message("2"), message("3")]);
+        assert_eq!(subscriber.dropped(), 1);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let hub = Hub::new(2);
+        // The policy applies to every clone of the hub.
+        hub.clone().set_policy(SlowConsumerPolicy::DropNewest);
+        assert_eq!(hub.policy(), SlowConsumerPolicy::DropNewest);
+        let mut subscriber = hub.subscribe();
+        assert_eq!(hub.publish(message("1")), 1);
+        assert_eq!(hub.publish(message("2")), 1);
+        assert_eq!(hub.publish(message("3")), 0);
+        assert_eq!(drain(&mut subscriber), vec![message("1"), message("2")]);
+        assert_eq!(subscriber.dropped(), 1);
+    }
+
+    #[test]
+    fn disconnect() {
+        let hub = Hub::new(1);
+        hub.set_policy(SlowConsumerPolicy::Disconnect);
+        let mut slow = hub.subscribe();
+        let mut fast = hub.subscribe();
+        hub.publish(message("1"));
+        assert_eq!(fast.next().now_or_never(), Some(Some(message("1"))));
+        hub.publish(message("2"));
+        assert!(slow.is_closed());
+        assert_eq!(slow.next().now_or_never(), Some(None));
+        assert_eq!(fast.next().now_or_never(), Some(Some(message("2"))));
+        assert_eq!(hub.subscriber_count(), 1);
+    }
+
+    #[test]
+    fn encoded() {
+        let hub = Hub::new(4);
+        let mut bytes = hub
+            .subscribe()
+            .encoded(SSECodec::new().with_initial_retry(10));
+        hub.publish(message("1"));
+        hub.publish(Event::message("bad\nevent", "2", None));
+        assert_eq!(
+            bytes.next().now_or_never().unwrap().unwrap().unwrap(),
+            b"retry: 10\n\ndata: 1\n\n"
+        );
+        assert!(matches!(
+            bytes.next().now_or_never(),
+            Some(Some(Err(Error::InvalidField(_))))
+        ));
+    }
+}
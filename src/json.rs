@@ -0,0 +1,239 @@
+use crate::{Error, Event, StreamState};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::Stream;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+impl Event {
+    /// Create a server-sent event message with `value` serialized as JSON in the data.
+    ///
+    /// Requires the `json` feature. Returns [`Error::JsonError`] if `value` can not be
+    /// serialized.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::Event;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Update {
+    ///     count: u32,
+    /// }
+    ///
+    /// let event = Event::json("update", &Update { count: 1 })?;
+    /// assert_eq!(event.to_string(), "event: update\ndata: {\"count\":1}\n");
+    /// # Ok::<(), sse_codec::Error>(())
+    /// ```
+    pub fn json<T: Serialize + ?Sized>(event: &str, value: &T) -> Result<Self, Error> {
+        // Compact JSON escapes line breaks in strings, so the data is always a single line.
+        let data = serde_json::to_string(value).map_err(|error| Error::JsonError {
+            error,
+            id: None,
+            event: event.to_string(),
+        })?;
+        Ok(Event::Message {
+            id: None,
+            event: event.to_string(),
+            data,
+            fields: vec![],
+        })
+    }
+
+    /// Deserialize the data of this message from JSON.
+    ///
+    /// Requires the `json` feature. Returns [`Error::JsonError`] with the ID and type of the
+    /// event if the data is not valid JSON for `T`. Events other than messages have no data, so
+    /// they always return an error.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::Event;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Update<'a> {
+    ///     name: &'a str,
+    /// }
+    ///
+    /// let event = Event::message("update", r#"{"name":"sse"}"#, None);
+    /// let update: Update = event.data_as()?;
+    /// assert_eq!(update.name, "sse");
+    /// # Ok::<(), sse_codec::Error>(())
+    /// ```
+    pub fn data_as<'a, T: Deserialize<'a>>(&'a self) -> Result<T, Error> {
+        let (id, event, data) = match self {
+            Event::Message {
+                id, event, data, ..
+            } => (id.as_deref(), &event[..], &data[..]),
+            _ => (None, "", ""),
+        };
+        serde_json::from_str(data).map_err(|error| Error::JsonError {
+            error,
+            id: id.map(String::from),
+            event: event.to_string(),
+        })
+    }
+}
+
+/// A stream of values deserialized from the data of JSON messages, returned from
+/// [`json_stream()`].
+#[derive(Debug)]
+pub struct JsonStream<S, T> {
+    events: S,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<S, T> JsonStream<S, T> {
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.events
+    }
+
+    /// Consume the adapter, returning the inner stream.
+    pub fn into_inner(self) -> S {
+        self.events
+    }
+}
+
+impl<S, T> Stream for JsonStream<S, T>
+where
+    S: Stream<Item = Result<Event, Error>> + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.events).poll_next(cx) {
+                Poll::Ready(Some(Ok(event @ Event::Message { .. }))) => {
+                    return Poll::Ready(Some(event.data_as()));
+                }
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<S: StreamState, T> StreamState for JsonStream<S, T> {
+    fn last_event_id(&self) -> Option<&str> {
+        self.events.last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.events.reconnection_time()
+    }
+}
+
+/// Deserialize the data of every message in a stream of events as JSON, returning a stream of
+/// `T`s.
+///
+/// Requires the `json` feature. This works with any decoding stream, such as
+/// [`DecodeStream`](crate::DecodeStream). Events other than messages are skipped. If the data of
+/// a message is not valid JSON for `T`, the stream returns [`Error::JsonError`] for that message
+/// and continues with the next one.
+///
+/// # Examples
+#[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+#[cfg_attr(feature = "futures_codec", doc = "```rust")]
+/// use sse_codec::{decode_stream, json_stream};
+/// use futures::stream::TryStreamExt; // for try_next()
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Update {
+///     count: u32,
+/// }
+///
+/// # async_std::task::block_on(async {
+/// let input: &[u8] = b"retry: 1000\ndata: {\"count\":1}\n\ndata: {\"count\":2}\n\n";
+/// let mut updates = json_stream::<Update, _>(decode_stream(input));
+/// assert_eq!(updates.try_next().await?, Some(Update { count: 1 }));
+/// assert_eq!(updates.try_next().await?, Some(Update { count: 2 }));
+/// assert_eq!(updates.try_next().await?, None);
+/// # Ok::<(), sse_codec::Error>(())
+/// # }).unwrap();
+/// ```
+pub fn json_stream<T, S>(events: S) -> JsonStream<S, T>
+where
+    S: Stream<Item = Result<Event, Error>>,
+    T: DeserializeOwned,
+{
+    JsonStream {
+        events,
+        value: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Update {
+        text: String,
+    }
+
+    #[test]
+    fn encode_multi_line_string() {
+        let event = Event::json(
+            "update",
+            &Update {
+                text: "a\nb".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            event.to_string(),
+            "event: update\ndata: {\"text\":\"a\\nb\"}\n"
+        );
+    }
+
+    #[test]
+    fn encode_error() {
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], 1);
+        assert!(matches!(
+            Event::json("map", &map),
+            Err(Error::JsonError { id: None, event, .. }) if event == "map"
+        ));
+    }
+
+    #[test]
+    fn decode_error() {
+        let event = Event::message("update", "{", "7");
+        assert!(matches!(
+            event.data_as::<Update>(),
+            Err(Error::JsonError { id: Some(id), event, .. }) if id == "7" && event == "update"
+        ));
+        assert!(Event::retry(10).data_as::<u32>().is_err());
+    }
+
+    #[test]
+    fn stream() {
+        let events = stream::iter(vec![
+            Ok(Event::json("a", &1).unwrap()),
+            Ok(Event::comment("skipped")),
+            Ok(Event::message("b", "x", "2")),
+            Err(Error::InvalidField("id".to_string())),
+            Ok(Event::json("c", &3).unwrap()),
+        ]);
+        let results: Vec<_> = block_on(json_stream::<u32, _>(events).collect());
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        assert!(matches!(
+            &results[1],
+            Err(Error::JsonError { id: Some(id), event, .. }) if id == "2" && event == "b"
+        ));
+        assert!(matches!(&results[2], Err(Error::InvalidField(_))));
+        assert_eq!(results[3].as_ref().unwrap(), &3);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::Event;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use futures_core::Stream;
+
+/// A source of timers for [`KeepAlive`], so it can be used with any async runtime.
+///
+/// This is implemented for functions and closures that take a `Duration` and return a future that
+/// completes after that time, such as `async_std::task::sleep` and `tokio::time::sleep`.
+pub trait Timer {
+    /// The future returned from [`Timer::sleep`].
+    type Sleep: Future<Output = ()>;
+
+    /// Create a future that completes after `duration` has passed.
+    fn sleep(&mut self, duration: Duration) -> Self::Sleep;
+}
+
+impl<F, Fut> Timer for F
+where
+    F: FnMut(Duration) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Sleep = Fut;
+
+    fn sleep(&mut self, duration: Duration) -> Self::Sleep {
+        self(duration)
+    }
+}
+
+/// A stream of events that inserts a keep-alive comment whenever the inner stream has been idle
+/// for a while, returned from [`keep_alive()`].
+#[derive(Debug)]
+pub struct KeepAlive<S, T: Timer> {
+    events: S,
+    timer: T,
+    interval: Duration,
+    comment: String,
+    sleep: Pin<Box<T::Sleep>>,
+    /// Has an event been sent since the current timer was started?
+    active: bool,
+}
+
+impl<S, T: Timer> KeepAlive<S, T> {
+    /// Set the text of the keep-alive comment. Defaults to an empty comment, which is encoded as
+    /// `:`.
+    ///
+    /// # Panics
+    /// Panics if `comment` contains a line break, because it would be sent as several comments.
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        assert!(
+            !comment.contains(['\r', '\n']),
+            "keep-alive comment contains a line break"
+        );
+        self.comment = comment.to_string();
+        self
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.events
+    }
+
+    /// Consume the adapter, returning the inner stream.
+    pub fn into_inner(self) -> S {
+        self.events
+    }
+}
+
+impl<S: Stream<Item = Event> + Unpin, T: Timer + Unpin> Stream for KeepAlive<S, T> {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match Pin::new(&mut this.events).poll_next(cx) {
+            Poll::Ready(Some(event)) => {
+                // Not all timers can be reset, and starting a new one for every event is wasteful,
+                // so only note the activity. The timer is restarted when it fires.
+                this.active = true;
+                return Poll::Ready(Some(event));
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => (),
+        }
+
+        while this.sleep.as_mut().poll(cx).is_ready() {
+            this.sleep = Box::pin(this.timer.sleep(this.interval));
+            if !core::mem::take(&mut this.active) {
+                return Poll::Ready(Some(Event::comment(&this.comment)));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// Insert a keep-alive comment into a stream of events whenever no event has been sent for
+/// `interval`.
+///
+/// Proxies and load balancers may close connections that have been idle for too long. The
+/// resulting stream can be sent into an [`EncodeStream`](crate::EncodeStream) to keep the
+/// connection alive. `timer` creates the timers, so any async runtime can be used.
+///
+/// Events do not restart the timer. Instead, when the timer fires after an event was sent, it is
+/// started again without sending a comment. A keep-alive comment is therefore sent between
+/// `interval` and twice `interval` after the last event, so pick an interval of at most half the
+/// idle timeout of the connection.
+///
+/// Keep-alive comments are only produced while the stream is polled, so this wraps the source of
+/// the events rather than the encoder. Events that are written with `send()` directly on the
+/// encoder bypass it and do not get keep-alive comments in between. To send events from
+/// elsewhere, pass them through a channel, as in the example below.
+///
+/// # Examples
+#[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+#[cfg_attr(feature = "futures_codec", doc = "```rust,no_run")]
+/// use sse_codec::{encode_stream, keep_alive, Event};
+/// use futures::{channel::mpsc, SinkExt, StreamExt};
+/// use std::time::Duration;
+///
+/// # async fn amain(output: async_std::net::TcpStream) -> Result<(), sse_codec::Error> {
+/// let (sender, receiver) = mpsc::channel::<Event>(16);
+/// // Hand `sender` to whatever produces the events.
+///
+/// let events = keep_alive(receiver, Duration::from_secs(15), async_std::task::sleep)
+///     .with_comment("keep-alive");
+/// let mut encoder = encode_stream(output);
+/// encoder.send_all(&mut events.map(Ok)).await?;
+/// # Ok(()) }
+/// ```
+pub fn keep_alive<S, T>(events: S, interval: Duration, mut timer: T) -> KeepAlive<S, T>
+where
+    S: Stream<Item = Event>,
+    T: Timer,
+{
+    KeepAlive {
+        events,
+        sleep: Box::pin(timer.sleep(interval)),
+        timer,
+        interval,
+        comment: String::new(),
+        active: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use futures::channel::{mpsc, oneshot};
+    use futures::{FutureExt, SinkExt, StreamExt};
+    use std::sync::Mutex;
+
+    /// The timers that were started, with their duration and a way to complete them.
+    type Sleeps = Vec<(Duration, Option<oneshot::Sender<()>>)>;
+
+    /// A timer that only fires when the test tells it to.
+    #[derive(Clone, Default)]
+    struct ManualTimer {
+        sleeps: Arc<Mutex<Sleeps>>,
+    }
+
+    impl ManualTimer {
+        /// Complete the `index`th timer that was started.
+        fn fire(&self, index: usize) {
+            let sender = self.sleeps.lock().unwrap()[index].1.take().unwrap();
+            let _ = sender.send(());
+        }
+
+        fn durations(&self) -> Vec<Duration> {
+            let sleeps = self.sleeps.lock().unwrap();
+            sleeps.iter().map(|(duration, _)| *duration).collect()
+        }
+    }
+
+    impl Timer for ManualTimer {
+        type Sleep = futures::future::Map<oneshot::Receiver<()>, fn(Result<(), oneshot::Canceled>)>;
+
+        fn sleep(&mut self, duration: Duration) -> Self::Sleep {
+            let (sender, receiver) = oneshot::channel();
+            self.sleeps.lock().unwrap().push((duration, Some(sender)));
+            receiver.map(|_| ())
+        }
+    }
+
+    #[test]
+    fn idle() {
+        let timer = ManualTimer::default();
+        let (_sender, receiver) = mpsc::channel::<Event>(1);
+        let mut events =
+            keep_alive(receiver, Duration::from_secs(15), timer.clone()).with_comment("ping");
+        assert_eq!(events.next().now_or_never(), None);
+        timer.fire(0);
+        assert_eq!(
+            events.next().now_or_never(),
+            Some(Some(Event::comment("ping")))
+        );
+        assert_eq!(events.next().now_or_never(), None);
+        timer.fire(1);
+        assert_eq!(
+            events.next().now_or_never(),
+            Some(Some(Event::comment("ping")))
+        );
+        assert_eq!(timer.durations(), vec![Duration::from_secs(15); 3]);
+    }
+
+    #[test]
+    fn activity_restarts_timer() {
+        let timer = ManualTimer::default();
+        let (mut sender, receiver) = mpsc::channel(1);
+        let mut events = keep_alive(receiver, Duration::from_secs(15), timer.clone());
+        sender
+            .send(Event::message("message", "hello", None))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            events.next().now_or_never(),
+            Some(Some(Event::message("message", "hello", None)))
+        );
+        sender
+            .send(Event::message("message", "again", None))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            events.next().now_or_never(),
+            Some(Some(Event::message("message", "again", None)))
+        );
+        assert_eq!(timer.durations().len(), 1);
+        // The events were sent after the first timer started, so it only restarts the timer.
+        timer.fire(0);
+        assert_eq!(events.next().now_or_never(), None);
+        timer.fire(1);
+        assert_eq!(events.next().now_or_never(), Some(Some(Event::comment(""))));
+
+        drop(sender);
+        assert_eq!(events.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    #[should_panic(expected = "line break")]
+    fn multi_line_comment() {
+        let (_sender, receiver) = mpsc::channel::<Event>(1);
+        let _ = keep_alive(receiver, Duration::from_secs(15), ManualTimer::default())
+            .with_comment("a\nb");
+    }
+}
@@ -3,7 +3,8 @@
 //! It emits or serializes full messages, and the meta-message `retry:`.
 //!
 //! # Examples
-//! ```rust,no_run
+#![cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+#![cfg_attr(feature = "futures_codec", doc = "```rust,no_run")]
 //! # async fn amain() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 //! use sse_codec::{decode_stream, Event};
 //! use futures::stream::TryStreamExt; // for try_next()
@@ -21,37 +22,129 @@
 //!         Event::Message { event, .. } if event == "stop" => {
 //!             break;
 //!         }
-//!         Event::Message { id, event, data } => {
+//!         Event::Message { id, event, data, .. } => {
 //!             if let Some(id) = id {
 //!                 // change the last event ID
 //!             }
 //!             // handle event here
 //!         }
+//!         Event::Comment { .. } => {
+//!             // only emitted if enabled with `SSECodec::with_comments()`
+//!         }
 //!     }
 //! }
 //! # Ok(()) }
 //! ```
-use futures_codec::{BytesMut, Decoder, Encoder, FramedRead, FramedWrite};
+//!
+//! # Backends
+//! The streams in the crate root use `futures_codec`. Other IO libraries are supported by these
+//! modules:
+//!
+//! * [`blocking`]: `std::io::Read` and `std::io::Write`.
+//! * `tokio`: `tokio-util` codecs, with the `tokio` feature.
+//! * `asynchronous_codec`: `asynchronous-codec`, with the `asynchronous-codec` feature.
+//!
+//! [`Parser`] can be used to drive the decoder without any IO library.
+//!
+//! # Features
+//! * `std` (default): `std::error::Error` and `std::io` support, and the [`blocking`] module.
+//! * `futures_codec` (default): the streams in the crate root. Implies `std`.
+//! * `tokio`: the `tokio` module. Implies `std`.
+//! * `asynchronous-codec`: the `asynchronous_codec` module. Implies `std`.
+//! * `serde`: `Serialize` and `Deserialize` for [`Event`]. Works without `std`.
+//! * `json`: JSON helpers [`Event::json`], [`Event::data_as`] and [`json_stream()`]. Implies
+//!   `std` and `serde`.
+//!
+//! Without the `std` feature, the crate is `no_std` and only needs `alloc`. [`Parser`], `FromStr`
+//! and `Display` for [`Event`] remain available to decode and encode events.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use bytes::BytesMut;
+use core::{fmt, str::FromStr};
+#[cfg(feature = "futures_codec")]
+use futures_codec::{Decoder, Encoder, FramedRead, FramedWrite};
+#[cfg(feature = "futures_codec")]
 use futures_io::{AsyncRead, AsyncWrite};
 use memchr::memchr2;
-use std::fmt::Write as _;
-use std::{fmt, str::FromStr};
+#[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+use memchr::memrchr2;
+
+#[cfg(feature = "asynchronous-codec")]
+pub mod asynchronous_codec;
+#[cfg(feature = "std")]
+mod auto_id;
+#[cfg(feature = "std")]
+pub mod blocking;
+mod byte_str;
+mod frame;
+#[cfg(feature = "std")]
+mod hub;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+mod keep_alive;
+mod parser;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "std")]
+pub use auto_id::{AutoId, Counter, IdGenerator, Timestamp, Ulid};
+pub use byte_str::ByteStr;
+pub use frame::{Field, Frame, FrameEncoder};
+#[cfg(feature = "std")]
+pub use hub::{EncodedSubscriber, Hub, SlowConsumerPolicy, Subscriber};
+#[cfg(feature = "json")]
+pub use json::{json_stream, JsonStream};
+#[cfg(feature = "std")]
+pub use keep_alive::{keep_alive, KeepAlive, Timer};
+pub use parser::Parser;
 
 /// An "event", either an incoming message or some meta-action that needs to be applied to the
 /// stream.
+///
+/// # Serialization
+/// With the `serde` feature, events implement `Serialize` and `Deserialize`, so they can be
+/// stored or sent to other services in any self-describing format, such as JSON or CBOR. The
+/// representation is stable: it will only change in a major version. In JSON, it looks like this:
+///
+/// ```json
+/// {"type":"message","id":"1","event":"add","data":"a\nb","fields":[["trace","1234"]]}
+/// {"type":"message","id":null,"event":"message","data":"","fields":[]}
+/// {"type":"retry","retry":3000}
+/// {"type":"comment","comment":"keep-alive"}
+/// ```
+///
+/// When deserializing, `id` and `fields` may be left out of a message.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "lowercase")
+)]
 pub enum Event {
     /// An incoming message.
     Message {
         /// The ID of this event.
         ///
         /// See also the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#concept-event-stream-last-event-id).
+        #[cfg_attr(feature = "serde", serde(default))]
         id: Option<String>,
         /// The event type. Defaults to "message" if no event name is provided.
         event: String,
         /// The data for this event.
         data: String,
+        /// Custom fields that are not part of the spec, such as `trace: 1234`, as name/value pairs
+        /// in the order they appeared.
+        ///
+        /// The decoder only collects these if enabled with [`SSECodec::with_custom_fields`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        fields: Vec<(String, String)>,
     },
     /// Set the _reconnection time_.
     ///
@@ -60,6 +153,13 @@ pub enum Event {
         /// The new reconnection time in milliseconds.
         retry: u64,
     },
+    /// A comment line, such as `: keep-alive`.
+    ///
+    /// Comments are only emitted by the decoder if enabled with [`SSECodec::with_comments`].
+    Comment {
+        /// The text of the comment, without the leading colon and space.
+        comment: String,
+    },
 }
 
 impl Event {
@@ -69,6 +169,7 @@ impl Event {
             id: id.into().map(String::from),
             event: event.to_string(),
             data: data.to_string(),
+            fields: vec![],
         }
     }
 
@@ -76,55 +177,471 @@ impl Event {
     pub fn retry(time: u64) -> Self {
         Event::Retry { retry: time }
     }
+
+    /// Create a comment.
+    pub fn comment(comment: &str) -> Self {
+        Event::Comment {
+            comment: comment.to_string(),
+        }
+    }
+
+    /// Create a server-sent event message, checking that the event type and ID can be encoded.
+    ///
+    /// Returns [`Error::InvalidField`] if the event type contains a line break, or the ID contains a
+    /// line break or a NULL character.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::{Error, Event};
+    ///
+    /// assert!(Event::try_message("add", "data", "1").is_ok());
+    /// assert!(matches!(
+    ///     Event::try_message("add\ndata: injected", "data", None),
+    ///     Err(Error::InvalidField(field)) if field == "event"
+    /// ));
+    /// ```
+    pub fn try_message<'a>(
+        event: &str,
+        data: &str,
+        id: impl Into<Option<&'a str>>,
+    ) -> Result<Self, Error> {
+        let message = Self::message(event, data, id);
+        message.validate()?;
+        Ok(message)
+    }
+
+    /// Check that this event can be encoded without changing its meaning.
+    ///
+    /// Event types, IDs and custom fields are written on a single line, so they may not contain
+    /// line breaks. IDs may not contain NULL characters either, because the decoder ignores those.
+    /// Custom field names may not be empty, contain a colon, start with a byte order mark, or be
+    /// one of the standard field names.
+    ///
+    /// Data and comments may contain any line breaks. Every line is written as a separate `data:`
+    /// or comment line, so the decoder turns `\r` and `\r\n` in data into `\n`, and a comment
+    /// with several lines into one comment per line.
+    ///
+    /// Every event the decoder can produce passes this check, and decodes to the same event after
+    /// it is encoded.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Event::Message {
+                id, event, fields, ..
+            } => {
+                if let Some(id) = id {
+                    check_value("id", id, ID_FORBIDDEN)?;
+                }
+                check_value("event", event, LINE_BREAKS)?;
+                for (name, value) in fields {
+                    check_custom_field(name, value)?;
+                }
+                Ok(())
+            }
+            Event::Retry { .. } | Event::Comment { .. } => Ok(()),
+        }
+    }
+
+    /// Make this event safe to encode according to `policy`.
+    #[cfg(feature = "std")]
+    fn apply_policy(self, policy: EncodePolicy) -> Result<Self, Error> {
+        match self {
+            Event::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => {
+                let id = match id {
+                    Some(id) => Some(encode_value("id", id, ID_FORBIDDEN, policy)?),
+                    None => None,
+                };
+                let event = encode_value("event", event, LINE_BREAKS, policy)?;
+                let mut encoded_fields = Vec::with_capacity(fields.len());
+                for (name, value) in fields {
+                    if let Some(field) = encode_custom_field(name, value, policy)? {
+                        encoded_fields.push(field);
+                    }
+                }
+                Ok(Event::Message {
+                    id,
+                    event,
+                    data,
+                    fields: encoded_fields,
+                })
+            }
+            Event::Retry { .. } | Event::Comment { .. } => Ok(self),
+        }
+    }
+}
+
+/// Characters that end a line.
+const LINE_BREAKS: &[char] = &['\r', '\n'];
+
+/// Characters that can not be used in an event ID.
+const ID_FORBIDDEN: &[char] = &['\r', '\n', '\0'];
+
+/// Characters that can not be used in a custom field name.
+const FIELD_NAME_FORBIDDEN: &[char] = &['\r', '\n', ':'];
+
+/// Can `name` not be used as a custom field name, even without forbidden characters?
+///
+/// An empty name would make a comment, a standard name would inject a field, and a byte order
+/// mark at the start of the stream would be removed.
+fn is_reserved_field_name(name: &str) -> bool {
+    name.is_empty()
+        || name.starts_with('\u{feff}')
+        || matches!(name, "event" | "data" | "id" | "retry")
+}
+
+/// Check that a custom field can be encoded as it is.
+fn check_custom_field(name: &str, value: &str) -> Result<(), Error> {
+    check_value(name, value, LINE_BREAKS)?;
+    check_value(name, name, FIELD_NAME_FORBIDDEN)?;
+    if is_reserved_field_name(name) {
+        return Err(Error::InvalidField(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Make a custom field safe to encode. Returns `None` if the field should be left out.
+#[cfg(feature = "std")]
+fn encode_custom_field(
+    name: String,
+    value: String,
+    policy: EncodePolicy,
+) -> Result<Option<(String, String)>, Error> {
+    let value = encode_value(&name, value, LINE_BREAKS, policy)?;
+    let label = name.clone();
+    let name = encode_value(&label, name, FIELD_NAME_FORBIDDEN, policy)?;
+    if is_reserved_field_name(&name) {
+        if policy == EncodePolicy::Reject {
+            return Err(Error::InvalidField(name));
+        }
+        return Ok(None);
+    }
+    Ok(Some((name, value)))
+}
+
+/// Check that the value of `field` does not contain any of the `forbidden` characters.
+fn check_value(field: &str, value: &str, forbidden: &[char]) -> Result<(), Error> {
+    if value.contains(forbidden) {
+        return Err(Error::InvalidField(field.to_string()));
+    }
+    Ok(())
+}
+
+/// Make the value of `field` safe to encode, if it contains any of the `forbidden` characters.
+#[cfg(feature = "std")]
+fn encode_value(
+    field: &str,
+    value: String,
+    forbidden: &[char],
+    policy: EncodePolicy,
+) -> Result<String, Error> {
+    if !value.contains(forbidden) {
+        return Ok(value);
+    }
+    match policy {
+        EncodePolicy::Reject => Err(Error::InvalidField(field.to_string())),
+        EncodePolicy::Strip => Ok(value.replace(forbidden, "")),
+        EncodePolicy::Replace => Ok(value.replace(forbidden, "\u{fffd}")),
+    }
+}
+
+/// An [`Event`] whose strings are backed by reference-counted [`ByteStr`]s.
+///
+/// Produced by [`BytesDecoder`]. When a field fits on a single line, its value shares the memory
+/// of the input buffer instead of being copied. The ID is the exception: the decoder keeps it as
+/// the _last event ID_ for later events, so it is copied to avoid holding on to the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytesEvent {
+    /// An incoming message.
+    Message {
+        /// The ID of this event.
+        ///
+        /// See also the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#concept-event-stream-last-event-id).
+        id: Option<ByteStr>,
+        /// The event type. Defaults to "message" if no event name is provided.
+        event: ByteStr,
+        /// The data for this event.
+        data: ByteStr,
+        /// Custom fields that are not part of the spec, such as `trace: 1234`, as name/value pairs
+        /// in the order they appeared.
+        ///
+        /// The decoder only collects these if enabled with [`SSECodec::with_custom_fields`].
+        fields: Vec<(ByteStr, ByteStr)>,
+    },
+    /// Set the _reconnection time_.
+    ///
+    /// See also the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#concept-event-stream-reconnection-time).
+    Retry {
+        /// The new reconnection time in milliseconds.
+        retry: u64,
+    },
+    /// A comment line, such as `: keep-alive`.
+    ///
+    /// Comments are only emitted by the decoder if enabled with [`SSECodec::with_comments`].
+    Comment {
+        /// The text of the comment, without the leading colon and space.
+        comment: ByteStr,
+    },
+}
+
+impl From<BytesEvent> for Event {
+    fn from(event: BytesEvent) -> Self {
+        match event {
+            BytesEvent::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => Event::Message {
+                id: id.map(String::from),
+                event: event.into(),
+                data: data.into(),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name.into(), value.into()))
+                    .collect(),
+            },
+            BytesEvent::Retry { retry } => Event::Retry { retry },
+            BytesEvent::Comment { comment } => Event::Comment {
+                comment: comment.into(),
+            },
+        }
+    }
+}
+
+impl From<Event> for BytesEvent {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => BytesEvent::Message {
+                id: id.map(ByteStr::from),
+                event: event.into(),
+                data: data.into(),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name.into(), value.into()))
+                    .collect(),
+            },
+            Event::Retry { retry } => BytesEvent::Retry { retry },
+            Event::Comment { comment } => BytesEvent::Comment {
+                comment: comment.into(),
+            },
+        }
+    }
+}
+
+/// A location in an event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// The line number, starting at 1.
+    pub line: u64,
+    /// The byte offset of the start of the line, starting at 0.
+    pub offset: u64,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position { line: 1, offset: 0 }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} (byte {})", self.line, self.offset)
+    }
+}
+
+/// A deviation from the Server-Sent Events spec, reported when validation is enabled with
+/// [`SSECodec::with_validation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A field other than `event`, `data`, `id` and `retry`.
+    UnknownField(String),
+    /// A `retry:` field whose value is not a base ten integer.
+    InvalidRetry(String),
+    /// An `id:` field containing U+0000 NULL.
+    NullInId,
+    /// A Byte Order Mark that is not at the very start of the stream.
+    MisplacedBom,
+    /// Data after the final blank line of the stream.
+    TrailingData,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownField(name) => write!(f, "unknown field {:?}", name),
+            Violation::InvalidRetry(value) => write!(f, "invalid retry value {:?}", value),
+            Violation::NullInId => write!(f, "event ID contains a NULL character"),
+            Violation::MisplacedBom => write!(f, "byte order mark not at the start of the stream"),
+            Violation::TrailingData => write!(f, "data after the final blank line"),
+        }
+    }
 }
 
 /// Errors that may occur while encoding or decoding server-sent event messages.
+///
+/// Some variants only exist with certain features enabled, and new variants may be added in minor
+/// releases, so a `match` on an `Error` needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// An I/O error occurred while reading or writing a stream.
+    #[cfg(feature = "std")]
     IoError(std::io::Error),
     /// Incoming data is not valid utf-8.
-    Utf8Error(std::str::Utf8Error),
-    /// An error occurred while writing an event message.
-    FmtError(std::fmt::Error),
-    /// Tried to read an incomplete frame.
-    IncompleteFrame,
+    Utf8Error(core::str::Utf8Error, Position),
+    /// A field of an outgoing event contains characters that cannot be encoded. Holds the name of
+    /// the field.
+    ///
+    /// See [`Event::validate`] and [`EncodePolicy`].
+    InvalidField(String),
+    /// The data of an event could not be serialized to or deserialized from JSON.
+    #[cfg(feature = "json")]
+    JsonError {
+        /// The error from `serde_json`.
+        error: serde_json::Error,
+        /// The ID of the event.
+        id: Option<String>,
+        /// The event type.
+        event: String,
+    },
+    /// Tried to read an incomplete frame. The position is the end of the input.
+    IncompleteFrame(Position),
+    /// An incoming line is longer than the configured [`Limits::max_line_length`].
+    LineTooLong(Position),
+    /// An incoming event has more data than the configured [`Limits::max_data_length`].
+    DataTooLong(Position),
+    /// An incoming event type is longer than the configured [`Limits::max_event_type_length`].
+    EventTypeTooLong(Position),
+    /// An incoming event ID is longer than the configured [`Limits::max_id_length`].
+    IdTooLong(Position),
+    /// More input is buffered than the configured [`Limits::max_buffer_size`].
+    BufferTooLarge(Position),
+    /// An incoming event has more custom fields than the configured [`Limits::max_fields`].
+    TooManyFields(Position),
+    /// The input deviates from the spec. Only reported if enabled with
+    /// [`SSECodec::with_validation`].
+    SpecViolation(Violation, Position),
+}
+
+impl Error {
+    /// The location in the input where the error occurred, if it is a decoding error.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::IoError(_) => None,
+            Error::InvalidField(_) => None,
+            #[cfg(feature = "json")]
+            Error::JsonError { .. } => None,
+            Error::Utf8Error(_, position)
+            | Error::IncompleteFrame(position)
+            | Error::LineTooLong(position)
+            | Error::DataTooLong(position)
+            | Error::EventTypeTooLong(position)
+            | Error::IdTooLong(position)
+            | Error::BufferTooLarge(position)
+            | Error::TooManyFields(position)
+            | Error::SpecViolation(_, position) => Some(*position),
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            #[cfg(feature = "std")]
             Error::IoError(inner) => inner.fmt(f),
-            Error::Utf8Error(inner) => inner.fmt(f),
-            Error::FmtError(inner) => inner.fmt(f),
-            Error::IncompleteFrame => write!(f, "incomplete frame"),
+            Error::Utf8Error(inner, position) => write!(f, "{} at {}", inner, position),
+            Error::InvalidField(name) => {
+                write!(
+                    f,
+                    "field {:?} contains characters that cannot be encoded",
+                    name
+                )
+            }
+            #[cfg(feature = "json")]
+            Error::JsonError { error, id, event } => {
+                write!(f, "invalid JSON in {:?} event", event)?;
+                if let Some(id) = id {
+                    write!(f, " with ID {:?}", id)?;
+                }
+                write!(f, ": {}", error)
+            }
+            Error::IncompleteFrame(position) => write!(f, "incomplete frame at {}", position),
+            Error::LineTooLong(position) => {
+                write!(f, "line exceeds the maximum length at {}", position)
+            }
+            Error::DataTooLong(position) => {
+                write!(f, "event data exceeds the maximum length at {}", position)
+            }
+            Error::EventTypeTooLong(position) => {
+                write!(f, "event type exceeds the maximum length at {}", position)
+            }
+            Error::IdTooLong(position) => {
+                write!(f, "event ID exceeds the maximum length at {}", position)
+            }
+            Error::BufferTooLarge(position) => {
+                write!(f, "buffered input exceeds the maximum size at {}", position)
+            }
+            Error::TooManyFields(position) => {
+                write!(f, "event has too many custom fields at {}", position)
+            }
+            Error::SpecViolation(violation, position) => write!(f, "{} at {}", violation, position),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Self::IoError(err)
     }
 }
 
-impl From<std::fmt::Error> for Error {
-    fn from(err: std::fmt::Error) -> Self {
-        Self::FmtError(err)
-    }
-}
-
-impl From<std::str::Utf8Error> for Error {
-    fn from(err: std::str::Utf8Error) -> Self {
-        Self::Utf8Error(err)
+/// Chop off a leading space (code point 0x20) from a field value.
+fn strip_leading_space(input: ByteStr) -> ByteStr {
+    if input.starts_with(' ') {
+        input.slice(1..)
+    } else {
+        input
     }
 }
 
-/// Chop off a leading space (code point 0x20) from a string slice.
-fn strip_leading_space(input: &str) -> &str {
-    input.strip_prefix(' ').unwrap_or(input)
+/// Split a string at every line terminator (CRLF, LF or CR), like the decoder does.
+///
+/// Unlike `str::lines`, a trailing line terminator results in an empty last line, so no line
+/// breaks are lost.
+fn split_lines(input: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(input);
+    core::iter::from_fn(move || {
+        let text = rest?;
+        match memchr2(b'\r', b'\n', text.as_bytes()) {
+            Some(pos) => {
+                let terminator_len = if text[pos..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                rest = Some(&text[pos + terminator_len..]);
+                Some(&text[..pos])
+            }
+            None => {
+                rest = None;
+                Some(text)
+            }
+        }
+    })
 }
 
 impl FromStr for Event {
@@ -133,36 +650,371 @@ impl FromStr for Event {
     /// Parse an event message from a string.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut codec = SSECodec::default();
-        for line in s.lines() {
-            if let Some(message @ Event::Message { .. }) = codec.parse_line(line) {
-                return Ok(message);
+        for (index, line) in split_lines(s).enumerate() {
+            let offset = line.as_ptr() as usize - s.as_ptr() as usize;
+            if line.is_empty() && offset == s.len() {
+                // This is the end of the input, not a blank line.
+                break;
+            }
+            codec.position = Position {
+                line: index as u64 + 1,
+                offset: offset as u64,
+            };
+            if let Some(message @ Parsed::Message { .. }) = codec.parse_line(line.into())? {
+                return Ok(message.into_event());
             }
         }
-        Err(Error::IncompleteFrame)
+        Err(Error::IncompleteFrame(Position {
+            line: split_lines(s).count() as u64,
+            offset: s.len() as u64,
+        }))
     }
 }
 
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_parts(f, |mut out| self.encode_parts("\n", &mut out))
+    }
+}
+
+impl Event {
+    /// The number of bytes the encoder writes for this event, including the blank line that ends
+    /// it.
+    ///
+    /// This can be used to size a buffer before encoding. It assumes the default
+    /// [`LineEnding::Lf`] and no byte order mark. Characters that are removed or replaced by the
+    /// [`EncodePolicy`] are not taken into account.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::Event;
+    ///
+    /// let event = Event::message("add", "a\nb", None);
+    /// assert_eq!(event.encoded_len(), "event: add\ndata: a\ndata: b\n\n".len());
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        self.encoded_len_with("\n")
+    }
+
+    /// The number of bytes the encoder writes for this event with `eol` as the line terminator.
+    fn encoded_len_with(&self, eol: &str) -> usize {
+        let mut len = eol.len();
+        self.encode_parts(eol, &mut |part| len += part.len());
+        len
+    }
+
+    /// Write the encoded event, including the blank line that ends it, reserving the exact size up
+    /// front.
+    #[cfg(feature = "std")]
+    fn encode_into(&self, eol: &str, dest: &mut impl EncodeBuf) {
+        dest.reserve(self.encoded_len_with(eol));
+        self.encode_parts(eol, &mut |part| dest.put(part.as_bytes()));
+        dest.put(eol.as_bytes());
+    }
+
+    /// Pass the encoded event to `out`, piece by piece, without the blank line that ends it.
+    fn encode_parts(&self, eol: &str, out: &mut impl FnMut(&str)) {
         match self {
-            Event::Message { id, event, data } => {
+            Event::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => {
                 if let Some(id) = id {
-                    if id.is_empty() {
-                        writeln!(f, "id")?;
-                    } else {
-                        writeln!(f, "id: {}", &id)?;
-                    }
+                    encode_field(out, eol, "id", id);
                 }
-                if event != "message" {
-                    writeln!(f, "event: {}", &event)?;
+                if event != DEFAULT_EVENT_TYPE {
+                    encode_field(out, eol, "event", event);
                 }
-
-                for line in data.lines() {
-                    writeln!(f, "data: {}", line)?;
+                for (name, value) in fields {
+                    encode_field(out, eol, name, value);
                 }
-                Ok(())
+                encode_data(out, eol, data);
             }
-            Event::Retry { retry } => writeln!(f, "retry: {}", retry),
+            Event::Retry { retry } => encode_retry(out, eol, *retry),
+            Event::Comment { comment } => encode_comment(out, eol, comment),
+        }
+    }
+}
+
+/// A buffer that encoded events are written to.
+#[cfg(feature = "std")]
+trait EncodeBuf {
+    fn reserve(&mut self, additional: usize);
+    fn put(&mut self, bytes: &[u8]);
+}
+
+#[cfg(feature = "std")]
+impl EncodeBuf for BytesMut {
+    fn reserve(&mut self, additional: usize) {
+        BytesMut::reserve(self, additional);
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+#[cfg(feature = "std")]
+impl EncodeBuf for Vec<u8> {
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+impl EncodeBuf for bytes1::BytesMut {
+    fn reserve(&mut self, additional: usize) {
+        bytes1::BytesMut::reserve(self, additional);
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Implement `Display` on top of an `encode_parts` method.
+fn display_parts(
+    f: &mut fmt::Formatter<'_>,
+    encode_parts: impl FnOnce(&mut dyn FnMut(&str)),
+) -> fmt::Result {
+    let mut result = Ok(());
+    encode_parts(&mut |part| {
+        if result.is_ok() {
+            result = f.write_str(part);
+        }
+    });
+    result
+}
+
+/// Encode a single field line. A field with an empty value is written as just its name.
+fn encode_field(out: &mut impl FnMut(&str), eol: &str, name: &str, value: &str) {
+    out(name);
+    if !value.is_empty() {
+        out(": ");
+        out(value);
+    }
+    out(eol);
+}
+
+/// Encode a `data:` line for every line in `data`.
+fn encode_data(out: &mut impl FnMut(&str), eol: &str, data: &str) {
+    for line in split_lines(data) {
+        encode_field(out, eol, "data", line);
+    }
+}
+
+/// Encode a comment line for every line in `comment`.
+fn encode_comment(out: &mut impl FnMut(&str), eol: &str, comment: &str) {
+    for line in split_lines(comment) {
+        if line.is_empty() {
+            out(":");
+            out(eol);
+        } else {
+            out(": ");
+            out(line);
+            out(eol);
+        }
+    }
+}
+
+/// Encode a `retry:` line.
+fn encode_retry(out: &mut impl FnMut(&str), eol: &str, retry: u64) {
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    let mut rest = retry;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    out("retry: ");
+    out(core::str::from_utf8(&digits[start..]).unwrap_or_default());
+    out(eol);
+}
+
+/// How the decoder should handle incoming data that is not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Mode {
+    /// Return an [`Error::Utf8Error`]. This is the default.
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// This is what the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#parsing-an-event-stream)
+    /// prescribes, and what browsers do.
+    Lossy,
+    /// Ignore lines that contain invalid UTF-8, as if they were never received.
+    SkipLine,
+}
+
+/// What the decoder should do when incoming data exceeds one of the configured [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitAction {
+    /// Return the error for the limit that was exceeded. This is the default.
+    ///
+    /// The offending event is discarded, so the stream can continue to be read after the error.
+    #[default]
+    Error,
+    /// Silently discard the offending event, and resume decoding after the next blank line.
+    Discard,
+}
+
+/// Limits on the size of incoming data, to protect against misbehaving servers.
+///
+/// All lengths and sizes are in bytes. None of the limits are enforced by default.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{LimitAction, Limits, SSECodec};
+///
+/// let limits = Limits::new()
+///     .max_line_length(64 * 1024)
+///     .max_data_length(1024 * 1024)
+///     .on_exceeded(LimitAction::Discard);
+/// let codec = SSECodec::new().with_limits(limits);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    line: Option<usize>,
+    data: Option<usize>,
+    event_type: Option<usize>,
+    id: Option<usize>,
+    buffer: Option<usize>,
+    fields: Option<usize>,
+    action: LimitAction,
+}
+
+impl Limits {
+    /// Create a set of limits that does not enforce anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum length of a single line, excluding the line terminator.
+    ///
+    /// Exceeding it results in [`Error::LineTooLong`].
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.line = Some(max);
+        self
+    }
+
+    /// Set the maximum size of the data buffer of a single event, including the newlines between
+    /// `data:` lines.
+    ///
+    /// Exceeding it results in [`Error::DataTooLong`].
+    pub fn max_data_length(mut self, max: usize) -> Self {
+        self.data = Some(max);
+        self
+    }
+
+    /// Set the maximum length of an event type.
+    ///
+    /// Exceeding it results in [`Error::EventTypeTooLong`].
+    pub fn max_event_type_length(mut self, max: usize) -> Self {
+        self.event_type = Some(max);
+        self
+    }
+
+    /// Set the maximum length of an event ID.
+    ///
+    /// Exceeding it results in [`Error::IdTooLong`].
+    pub fn max_id_length(mut self, max: usize) -> Self {
+        self.id = Some(max);
+        self
+    }
+
+    /// Set the maximum number of input bytes that may be buffered while waiting for the end of a
+    /// line.
+    ///
+    /// Exceeding it results in [`Error::BufferTooLarge`].
+    pub fn max_buffer_size(mut self, max: usize) -> Self {
+        self.buffer = Some(max);
+        self
+    }
+
+    /// Set the maximum number of custom fields in a single event. Only applies if custom fields
+    /// are enabled with [`SSECodec::with_custom_fields`].
+    ///
+    /// Exceeding it results in [`Error::TooManyFields`].
+    pub fn max_fields(mut self, max: usize) -> Self {
+        self.fields = Some(max);
+        self
+    }
+
+    /// Set what to do when a limit is exceeded.
+    pub fn on_exceeded(mut self, action: LimitAction) -> Self {
+        self.action = action;
+        self
+    }
+}
+
+/// Check if `len` is over an optional limit.
+fn exceeds(len: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| len > limit)
+}
+
+/// How the decoder should handle `retry:` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetryMode {
+    /// Emit an [`Event::Retry`] as soon as a `retry:` field is received, even if it is in the
+    /// middle of a message. This is the default.
+    #[default]
+    Event,
+    /// Only update the reconnection time of the stream, which can be read using
+    /// [`StreamState::reconnection_time`]. No [`Event::Retry`]s are emitted.
+    ///
+    /// This matches the spec, where the reconnection time is a property of the stream rather than
+    /// an event.
+    State,
+}
+
+/// What the encoder should do with events that cannot be encoded as they are, such as an event
+/// type containing a line break.
+///
+/// Without this, an untrusted event type or ID could inject fields or split a frame. See
+/// [`Event::validate`] for the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodePolicy {
+    /// Return [`Error::InvalidField`]. This is the default.
+    #[default]
+    Reject,
+    /// Remove the offending characters. Invalid custom fields are left out.
+    Strip,
+    /// Replace the offending characters with U+FFFD REPLACEMENT CHARACTER. Invalid custom fields
+    /// are left out.
+    Replace,
+}
+
+/// The line terminator that the encoder writes.
+///
+/// The spec allows all of these, and the decoder accepts all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`. This is the default.
+    #[default]
+    Lf,
+    /// `\r\n`.
+    CrLf,
+    /// `\r`.
+    Cr,
+}
+
+impl LineEnding {
+    /// The line terminator as a string.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
         }
     }
 }
@@ -170,148 +1022,1160 @@ impl fmt::Display for Event {
 /// Encoder/decoder for server-sent event streams.
 #[derive(Debug, Default, Clone)]
 pub struct SSECodec {
+    /// What to do with invalid UTF-8 input.
+    utf8_mode: Utf8Mode,
+    /// Size limits on incoming data.
+    limits: Limits,
+    /// How many bytes at the start of the input buffer are known not to contain a line
+    /// terminator.
+    scanned: ScanCursor,
+    /// Is the remainder of the current line being discarded?
+    skip_line: bool,
+    /// Is the remainder of the current event being discarded?
+    skip_event: bool,
+    /// Should comments be emitted as events?
+    comments: bool,
+    /// Should unknown fields be collected?
+    custom_fields: bool,
+    /// Should `retry:` fields be emitted as events?
+    retry_mode: RetryMode,
+    /// Should spec deviations be reported as errors?
+    validation: bool,
+    /// What to do with events that cannot be encoded as they are.
+    encode_policy: EncodePolicy,
+    /// The line terminator to encode.
+    line_ending: LineEnding,
+    /// Should the encoder write a byte order mark?
+    bom: bool,
+    /// The length of the padding comment to write before the first event.
+    padding: usize,
+    /// The `retry:` field to write before the first event.
+    initial_retry: Option<u64>,
+    /// Has the byte order mark, padding and initial `retry:` been written?
+    #[cfg(feature = "std")]
+    wrote_preamble: bool,
+    /// How many invalid UTF-8 sequences have been encountered so far.
+    utf8_errors: u64,
+    /// The number of bytes that have been consumed from the input.
+    consumed: u64,
+    /// The position of the line that is currently being parsed.
+    position: Position,
+    /// The position of the next line.
+    next_line: Position,
     /// Have we processed the optional Byte Order Marker on the first line?
     processed_bom: bool,
     /// Was the last character of the previous line a \r?
     last_was_cr: bool,
+    /// The position of the first field line since the last blank line.
+    event_start: Option<Position>,
     /// The _last event ID_ buffer.
-    last_event_id: Option<String>,
+    last_event_id: Option<ByteStr>,
+    /// The event stream's _reconnection time_, if one was received.
+    reconnection_time: Option<u64>,
     /// The _event type_ buffer.
-    event_type: Option<String>,
+    event_type: Option<ByteStr>,
     /// The _data_ buffer.
-    data: String,
+    data: DataBuffer,
+    /// Custom fields in the current event.
+    fields: Vec<(ByteStr, ByteStr)>,
+    /// Complete lines from framing libraries that use a different version of `bytes`.
+    #[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+    foreign_input: BytesMut,
+    /// How many bytes at the start of the foreign input buffer are known not to contain a line
+    /// terminator.
+    #[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+    foreign_scanned: ScanCursor,
+}
+
+/// How many bytes at the start of an input buffer are known not to contain a line terminator.
+///
+/// The input buffer is not owned by the codec, so a cloned codec may be used with a different
+/// buffer. Cloning resets the cursor, which only means the buffer is scanned again.
+#[derive(Debug, Default)]
+struct ScanCursor(usize);
+
+impl ScanCursor {
+    /// The position to continue scanning `src` from.
+    fn get(&self, src: &[u8]) -> usize {
+        self.0.min(src.len())
+    }
+}
+
+impl Clone for ScanCursor {
+    fn clone(&self) -> Self {
+        ScanCursor(0)
+    }
+}
+
+/// The _data_ buffer of the event that is currently being parsed.
+///
+/// A single line of data is kept as a slice of the input. Only when a second line comes in, the
+/// lines are copied into a `String`.
+#[derive(Debug, Default, Clone)]
+enum DataBuffer {
+    /// No `data:` lines have been received.
+    #[default]
+    Empty,
+    /// One `data:` line has been received. The trailing newline is implied.
+    Shared(ByteStr),
+    /// Multiple `data:` lines have been received. Each line is followed by a newline.
+    Owned(String),
+}
+
+impl DataBuffer {
+    /// The length of the buffer contents, including the newline after each line.
+    fn len(&self) -> usize {
+        match self {
+            DataBuffer::Empty => 0,
+            DataBuffer::Shared(line) => line.len() + 1,
+            DataBuffer::Owned(lines) => lines.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, DataBuffer::Empty)
+    }
+
+    fn push_line(&mut self, line: ByteStr) {
+        *self = match core::mem::take(self) {
+            DataBuffer::Empty => DataBuffer::Shared(line),
+            DataBuffer::Shared(first) => {
+                let mut lines = String::with_capacity(first.len() + line.len() + 2);
+                lines += &first;
+                lines.push('\n');
+                lines += &line;
+                lines.push('\n');
+                DataBuffer::Owned(lines)
+            }
+            DataBuffer::Owned(mut lines) => {
+                lines += &line;
+                lines.push('\n');
+                DataBuffer::Owned(lines)
+            }
+        };
+    }
+
+    /// Get the contents without the final newline.
+    fn into_string(self) -> String {
+        match self {
+            DataBuffer::Empty => String::new(),
+            DataBuffer::Shared(line) => line.into(),
+            DataBuffer::Owned(mut lines) => {
+                lines.pop();
+                lines
+            }
+        }
+    }
+
+    /// Get the contents without the final newline.
+    fn into_byte_str(self) -> ByteStr {
+        match self {
+            DataBuffer::Empty => ByteStr::default(),
+            DataBuffer::Shared(line) => line,
+            DataBuffer::Owned(mut lines) => {
+                lines.pop();
+                lines.into()
+            }
+        }
+    }
+}
+
+/// The name that is used for events that do not have an `event:` field.
+const DEFAULT_EVENT_TYPE: &str = "message";
+
+/// A parsed event, before being converted into an [`Event`] or a [`BytesEvent`].
+#[derive(Debug)]
+enum Parsed {
+    Message {
+        id: Option<ByteStr>,
+        event: Option<ByteStr>,
+        data: DataBuffer,
+        fields: Vec<(ByteStr, ByteStr)>,
+    },
+    Retry(u64),
+    Comment(ByteStr),
+}
+
+impl Parsed {
+    fn into_event(self) -> Event {
+        match self {
+            Parsed::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => Event::Message {
+                id: id.map(String::from),
+                event: event.map_or_else(|| DEFAULT_EVENT_TYPE.to_string(), String::from),
+                data: data.into_string(),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name.into(), value.into()))
+                    .collect(),
+            },
+            Parsed::Retry(retry) => Event::Retry { retry },
+            Parsed::Comment(comment) => Event::Comment {
+                comment: comment.into(),
+            },
+        }
+    }
+
+    fn into_bytes_event(self) -> BytesEvent {
+        match self {
+            Parsed::Message {
+                id,
+                event,
+                data,
+                fields,
+            } => BytesEvent::Message {
+                id,
+                event: event.unwrap_or_else(|| ByteStr::from_static(DEFAULT_EVENT_TYPE)),
+                data: data.into_byte_str(),
+                fields,
+            },
+            Parsed::Retry(retry) => BytesEvent::Retry { retry },
+            Parsed::Comment(comment) => BytesEvent::Comment { comment },
+        }
+    }
 }
 
 impl SSECodec {
-    fn take_message(&mut self) -> Option<Event> {
-        fn default_event_name() -> String {
-            "message".to_string()
+    /// Create a codec with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure how the decoder handles invalid UTF-8 input.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::{SSECodec, Utf8Mode};
+    ///
+    /// let codec = SSECodec::new().with_utf8_mode(Utf8Mode::Lossy);
+    /// ```
+    pub fn with_utf8_mode(mut self, mode: Utf8Mode) -> Self {
+        self.utf8_mode = mode;
+        self
+    }
+
+    /// How the decoder handles invalid UTF-8 input.
+    pub fn utf8_mode(&self) -> Utf8Mode {
+        self.utf8_mode
+    }
+
+    /// The number of invalid UTF-8 sequences the decoder has encountered so far.
+    ///
+    /// In [`Utf8Mode::Lossy`], this is the number of U+FFFD REPLACEMENT CHARACTERs that were
+    /// inserted into the decoded data. In [`Utf8Mode::SkipLine`], it counts the invalid sequences
+    /// in the lines that were skipped.
+    pub fn utf8_errors(&self) -> u64 {
+        self.utf8_errors
+    }
+
+    /// Configure size limits on incoming data.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The size limits on incoming data.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Configure whether the decoder emits comment lines as [`Event::Comment`]s.
+    ///
+    /// By default, comments are ignored, as the spec requires.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::SSECodec;
+    ///
+    /// let codec = SSECodec::new().with_comments(true);
+    /// ```
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Whether the decoder emits comment lines.
+    pub fn comments(&self) -> bool {
+        self.comments
+    }
+
+    /// Configure whether the decoder collects fields other than `event`, `data`, `id` and
+    /// `retry` into [`Event::Message`]'s `fields`.
+    ///
+    /// By default, unknown fields are ignored, as the spec requires.
+    pub fn with_custom_fields(mut self, custom_fields: bool) -> Self {
+        self.custom_fields = custom_fields;
+        self
+    }
+
+    /// Whether the decoder collects unknown fields.
+    pub fn custom_fields(&self) -> bool {
+        self.custom_fields
+    }
+
+    /// Configure how the decoder handles `retry:` fields.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::{RetryMode, SSECodec};
+    ///
+    /// let codec = SSECodec::new().with_retry_mode(RetryMode::State);
+    /// ```
+    pub fn with_retry_mode(mut self, mode: RetryMode) -> Self {
+        self.retry_mode = mode;
+        self
+    }
+
+    /// How the decoder handles `retry:` fields.
+    pub fn retry_mode(&self) -> RetryMode {
+        self.retry_mode
+    }
+
+    /// Configure whether the decoder reports deviations from the spec as
+    /// [`Error::SpecViolation`]s, instead of silently ignoring them.
+    ///
+    /// This is meant for testing event stream producers. After a violation is reported, decoding
+    /// can continue as normal. Unknown fields are not reported if custom fields are enabled with
+    /// [`SSECodec::with_custom_fields`].
+    ///
+    /// With `decode_stream_with()` and the `asynchronous_codec` streams, trailing data at the end
+    /// of the stream can only be detected if the last line is incomplete, because the underlying
+    /// `FramedRead` stops without calling `decode_eof` once its buffer is empty. [`Parser::finish`]
+    /// and the `blocking` and `tokio` decoders always detect it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::{Error, Parser, SSECodec, Violation};
+    ///
+    /// let mut parser = Parser::with_codec(SSECodec::new().with_validation(true));
+    /// parser.feed(b"data: 1\nretry: soon\n\n");
+    /// match parser.next_event() {
+    ///     Err(Error::SpecViolation(Violation::InvalidRetry(value), position)) => {
+    ///         assert_eq!(value, "soon");
+    ///         assert_eq!(position.line, 2);
+    ///         assert_eq!(position.offset, 8);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Whether the decoder reports deviations from the spec.
+    pub fn validation(&self) -> bool {
+        self.validation
+    }
+
+    /// Set what the encoder does with event types, IDs, custom fields, data and comments that
+    /// cannot be encoded, because they contain line breaks or other invalid characters.
+    ///
+    /// # Examples
+    #[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+    #[cfg_attr(feature = "futures_codec", doc = "```rust")]
+    /// use sse_codec::{encode_stream, EncodePolicy, Event, SSECodec};
+    /// use futures::sink::SinkExt;
+    /// use futures_codec::FramedWrite;
+    ///
+    /// # async_std::task::block_on(async {
+    /// let mut output = vec![];
+    /// let codec = SSECodec::new().with_encode_policy(EncodePolicy::Strip);
+    /// let mut events = FramedWrite::new(&mut output, codec);
+    /// events.send(Event::message("add\ndata: injected", "1", None)).await?;
+    /// assert_eq!(output, b"event: adddata: injected\ndata: 1\n\n");
+    /// # Ok::<(), sse_codec::Error>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn with_encode_policy(mut self, policy: EncodePolicy) -> Self {
+        self.encode_policy = policy;
+        self
+    }
+
+    /// What the encoder does with events that cannot be encoded as they are.
+    pub fn encode_policy(&self) -> EncodePolicy {
+        self.encode_policy
+    }
+
+    /// Set the line terminator that the encoder writes. Defaults to [`LineEnding::Lf`].
+    ///
+    /// Line breaks inside data and comments are written with this terminator as well.
+    ///
+    /// # Examples
+    #[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+    #[cfg_attr(feature = "futures_codec", doc = "```rust")]
+    /// use sse_codec::{encode_stream, Event, LineEnding, SSECodec};
+    /// use futures::sink::SinkExt;
+    /// use futures_codec::FramedWrite;
+    ///
+    /// # async_std::task::block_on(async {
+    /// let mut output = vec![];
+    /// let codec = SSECodec::new().with_line_ending(LineEnding::CrLf).with_bom(true);
+    /// let mut events = FramedWrite::new(&mut output, codec);
+    /// events.send(Event::message("add", "a\nb", None)).await?;
+    /// events.send(Event::retry(10)).await?;
+    /// assert_eq!(
+    ///     output,
+    ///     "\u{feff}event: add\r\ndata: a\r\ndata: b\r\n\r\nretry: 10\r\n\r\n".as_bytes()
+    /// );
+    /// # Ok::<(), sse_codec::Error>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// The line terminator that the encoder writes.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Set whether the encoder writes a byte order mark before the first event. Defaults to
+    /// `false`.
+    ///
+    /// The decoder skips a byte order mark at the start of the stream, so this does not change
+    /// the meaning of the stream.
+    pub fn with_bom(mut self, bom: bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Whether the encoder writes a byte order mark before the first event.
+    pub fn bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Set the length of a comment that the encoder writes before the first event. Defaults to
+    /// 0, which writes no comment.
+    ///
+    /// Some proxies and older browsers buffer the start of a response, usually up to 2 KiB, before
+    /// passing anything on. Padding the stream with a comment of that size makes the first event
+    /// arrive right away. The comment consists of a colon followed by `len` spaces.
+    ///
+    /// # Examples
+    #[cfg_attr(not(feature = "futures_codec"), doc = "```ignore")]
+    #[cfg_attr(feature = "futures_codec", doc = "```rust")]
+    /// use sse_codec::{encode_stream_with, Event, SSECodec};
+    /// use futures::sink::SinkExt;
+    ///
+    /// # async_std::task::block_on(async {
+    /// let mut output = vec![];
+    /// let codec = SSECodec::new().with_padding(2048).with_initial_retry(5000);
+    /// let mut events = encode_stream_with(&mut output, codec);
+    /// events.send(Event::message("message", "hello", None)).await?;
+    /// drop(events);
+    /// assert_eq!(output.len(), 2050 + "retry: 5000\n\ndata: hello\n\n".len());
+    /// assert!(output.ends_with(b"   \nretry: 5000\n\ndata: hello\n\n"));
+    /// # Ok::<(), sse_codec::Error>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn with_padding(mut self, len: usize) -> Self {
+        self.padding = len;
+        self
+    }
+
+    /// The length of the comment that the encoder writes before the first event.
+    pub fn padding(&self) -> usize {
+        self.padding
+    }
+
+    /// Set a _reconnection time_ in milliseconds that the encoder writes as a `retry:` field
+    /// before the first event. Defaults to `None`, which writes no `retry:` field.
+    ///
+    /// This is written after the byte order mark and the padding comment, if those are enabled.
+    pub fn with_initial_retry(mut self, time: impl Into<Option<u64>>) -> Self {
+        self.initial_retry = time.into();
+        self
+    }
+
+    /// The _reconnection time_ that the encoder writes before the first event.
+    pub fn initial_retry(&self) -> Option<u64> {
+        self.initial_retry
+    }
+
+    /// Start decoding with the given _last event ID_, for example when reconnecting to a stream.
+    ///
+    /// Messages that do not have an `id:` field will carry this ID, until the stream sets a new
+    /// one.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use sse_codec::{SSECodec, StreamState};
+    ///
+    /// let old = SSECodec::new().with_last_event_id("42").with_reconnection_time(3000);
+    /// // ... the connection drops ...
+    /// let new = SSECodec::new()
+    ///     .with_last_event_id(old.last_event_id().unwrap())
+    ///     .with_reconnection_time(old.reconnection_time().unwrap());
+    /// ```
+    pub fn with_last_event_id(mut self, id: &str) -> Self {
+        self.last_event_id = Some(id.into());
+        self
+    }
+
+    /// Start decoding with the given _reconnection time_ in milliseconds.
+    pub fn with_reconnection_time(mut self, time: u64) -> Self {
+        self.reconnection_time = Some(time);
+        self
+    }
+
+    /// Discard the event that is currently being parsed after a limit was exceeded, and skip
+    /// lines until the next blank line.
+    fn limit_exceeded(&mut self, err: Error) -> Result<(), Error> {
+        self.data = DataBuffer::Empty;
+        self.event_type = None;
+        self.fields.clear();
+        self.event_start = None;
+        self.skip_event = true;
+        match self.limits.action {
+            LimitAction::Error => Err(err),
+            LimitAction::Discard => Ok(()),
         }
+    }
 
+    fn take_message(&mut self) -> Option<Parsed> {
+        self.event_start = None;
         if self.data.is_empty() {
             // If the data buffer is an empty string, set the data buffer and the event type buffer to the empty string [and return.]
             self.event_type.take();
+            self.fields.clear();
             None
         } else {
-            if self.data.ends_with('\n') {
-                self.data.pop();
-            }
-            Some(Event::Message {
+            Some(Parsed::Message {
                 // The _last event ID_ buffer persists between messages.
                 id: self.last_event_id.clone(),
-                event: self.event_type.take().unwrap_or_else(default_event_name),
-                data: std::mem::take(&mut self.data),
+                event: self.event_type.take(),
+                data: core::mem::take(&mut self.data),
+                fields: core::mem::take(&mut self.fields),
             })
         }
     }
 
-    fn parse_line(&mut self, line: &str) -> Option<Event> {
-        let mut parts = line.splitn(2, ':');
-        match (parts.next(), parts.next()) {
+    fn parse_line(&mut self, line: ByteStr) -> Result<Option<Parsed>, Error> {
+        let (field, value) = match line.find(':') {
+            Some(colon) => (&line[..colon], Some(line.slice(colon + 1..))),
+            None => (&line[..], None),
+        };
+        if !field.is_empty() && self.event_start.is_none() {
+            self.event_start = Some(self.position);
+        }
+        match (field, value) {
             // If the field name is "retry":
-            (Some("retry"), Some(value)) if value.chars().all(|c| c.is_ascii_digit()) => {
+            ("retry", value) => {
+                let value = value.map(strip_leading_space).unwrap_or_default();
                 // If the field value consists of only ASCII digits, then interpret the field value
                 // as an integer in base ten, and set the event stream's reconnection time to that
                 // integer. Otherwise, ignore the field.
-                if let Ok(time) = value.parse::<u64>() {
-                    return Some(Event::Retry { retry: time });
+                match value.parse::<u64>() {
+                    Ok(time) if value.chars().all(|c| c.is_ascii_digit()) => {
+                        self.reconnection_time = Some(time);
+                        if self.retry_mode == RetryMode::Event {
+                            return Ok(Some(Parsed::Retry(time)));
+                        }
+                    }
+                    _ if self.validation => {
+                        return Err(self.violation(Violation::InvalidRetry(value.into())));
+                    }
+                    _ => (),
                 }
             }
             // If the field name is "event":
-            (Some("event"), Some(value)) => {
+            ("event", value) => {
+                let value = value.map(strip_leading_space).unwrap_or_default();
+                if exceeds(value.len(), self.limits.event_type) {
+                    return Err(Error::EventTypeTooLong(self.position));
+                }
                 // Set the event type buffer to field value.
-                self.event_type = Some(strip_leading_space(value).to_string());
+                self.event_type = Some(value);
             }
             // If the field name is "data":
-            (Some("data"), value) => {
-                // Append the field value to the data buffer,
-                if let Some(value) = value {
-                    self.data += strip_leading_space(value);
+            ("data", value) => {
+                let value = value.map(strip_leading_space).unwrap_or_default();
+                // The buffer has a newline after every line, which is between this line and the
+                // previous one once the data is joined.
+                if exceeds(self.data.len() + value.len(), self.limits.data) {
+                    return Err(Error::DataTooLong(self.position));
                 }
-                // then append a single U+000A LINE FEED (LF) character to the data buffer.
-                self.data.push('\n');
+                // Append the field value to the data buffer, then append a single U+000A LINE FEED
+                // (LF) character to the data buffer.
+                self.data.push_line(value);
             }
             // If the field name is "id":
-            (Some("id"), Some(id_str)) if !id_str.contains(char::from(0)) => {
+            ("id", value) if !value.as_ref().is_some_and(|id| id.contains('\0')) => {
+                let id_str = value.map(strip_leading_space).unwrap_or_default();
+                if exceeds(id_str.len(), self.limits.id) {
+                    return Err(Error::IdTooLong(self.position));
+                }
                 // If the field value does not contain U+0000 NULL, then set the last event ID buffer to the field value.
                 // Otherwise, ignore the field.
-                self.last_event_id = Some(strip_leading_space(id_str).to_string());
+                // The last event ID outlives the event, so copy it instead of keeping the whole input
+                // buffer alive.
+                self.last_event_id = Some(ByteStr::from(&*id_str));
+            }
+            ("id", _) if self.validation => {
+                return Err(self.violation(Violation::NullInId));
             }
             // Comment
-            (Some(""), Some(_)) => (),
+            ("", Some(comment)) if self.comments => {
+                return Ok(Some(Parsed::Comment(strip_leading_space(comment))));
+            }
+            ("", Some(_)) => (),
             // End of frame
-            (Some(""), None) => {
-                return self.take_message();
+            ("", None) => {
+                return Ok(self.take_message());
+            }
+            // Otherwise: the field is ignored, unless custom fields are enabled.
+            (name, value)
+                if self.custom_fields && !matches!(name, "event" | "data" | "id" | "retry") =>
+            {
+                if exceeds(self.fields.len() + 1, self.limits.fields) {
+                    return Err(Error::TooManyFields(self.position));
+                }
+                let name = line.slice(..name.len());
+                let value = value.map(strip_leading_space).unwrap_or_default();
+                self.fields.push((name, value));
+            }
+            (name, _) if self.validation && !matches!(name, "event" | "data" | "id" | "retry") => {
+                return Err(self.violation(Violation::UnknownField(name.to_string())));
             }
             _ => (),
         }
-        None
+        Ok(None)
     }
-}
 
-impl Decoder for SSECodec {
-    type Item = Event;
-    type Error = Error;
+    /// Forget where scanning the input buffer left off, because the codec is going to be used with
+    /// a new buffer.
+    fn reset_input(&mut self) {
+        self.scanned = ScanCursor(0);
+        #[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+        {
+            self.foreign_scanned = ScanCursor(0);
+        }
+    }
 
-    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        while let Some(pos) = memchr2(b'\r', b'\n', src) {
-            let line = src.split_to(pos + 1);
+    fn violation(&self, violation: Violation) -> Error {
+        Error::SpecViolation(violation, self.position)
+    }
+
+    fn decode_parsed(&mut self, src: &mut BytesMut) -> Result<Option<Parsed>, Error> {
+        // Only look for line terminators in bytes that have not been scanned before, so a long line
+        // arriving in many small chunks does not have to be rescanned from the start every time.
+        while let Some(pos) = memchr2(b'\r', b'\n', &src[self.scanned.get(src)..]) {
+            let pos = self.scanned.get(src) + pos;
+            self.scanned = ScanCursor(0);
+            let line = src.split_to(pos + 1).freeze();
+            self.consumed += line.len() as u64;
 
             // treat \r\n as one newline
             if pos == 0 && line == "\n" && self.last_was_cr {
                 self.last_was_cr = false;
+                self.next_line.offset = self.consumed;
                 continue;
             }
             self.last_was_cr = line.last() == Some(&b'\r');
+            self.position = self.next_line;
+            self.next_line = Position {
+                line: self.position.line + 1,
+                offset: self.consumed,
+            };
 
             // get rid of the '\n' at the end
-            let line = std::str::from_utf8(&line[..pos])?;
-            // get rid of the BOM at the start
+            let line = line.slice(..pos);
+
+            if self.skip_line {
+                // This is the tail end of a line that was already discarded.
+                self.skip_line = false;
+                continue;
+            }
+            if self.skip_event {
+                // Skip lines until the blank line at the end of the discarded event.
+                self.skip_event = !line.is_empty();
+                continue;
+            }
+            if exceeds(line.len(), self.limits.line) {
+                self.limit_exceeded(Error::LineTooLong(self.position))?;
+                continue;
+            }
+
+            let line = match self.utf8_mode {
+                Utf8Mode::Strict => {
+                    ByteStr::from_utf8(line).map_err(|err| Error::Utf8Error(err, self.position))?
+                }
+                Utf8Mode::Lossy => {
+                    let (line, replacements) = ByteStr::from_utf8_lossy(line);
+                    self.utf8_errors += replacements as u64;
+                    line
+                }
+                Utf8Mode::SkipLine => match ByteStr::from_utf8_lossy(line) {
+                    (line, 0) => line,
+                    (_, errors) => {
+                        self.utf8_errors += errors as u64;
+                        continue;
+                    }
+                },
+            };
+            // get rid of the BOM at the start of the stream
             let line = if line.starts_with('\u{feff}') && !self.processed_bom {
-                self.processed_bom = true;
-                &line[3..]
+                line.slice(3..)
             } else {
                 line
             };
-            if let Some(event) = self.parse_line(line) {
-                return Ok(Some(event));
+            self.processed_bom = true;
+            if self.validation && line.starts_with('\u{feff}') {
+                return Err(self.violation(Violation::MisplacedBom));
+            }
+            match self.parse_line(line) {
+                Ok(Some(event)) => return Ok(Some(event)),
+                Ok(None) => (),
+                Err(err @ Error::SpecViolation(..)) => return Err(err),
+                Err(err) => self.limit_exceeded(err)?,
             }
         }
+
+        // There is no complete line left, so anything in the buffer is the start of a line that
+        // has not fully arrived yet.
+        if let Some(err) = self.partial_line_error(src.len()) {
+            let len = src.len();
+            src.clear();
+            self.discard_partial_line(len, err)?;
+        }
+        self.scanned = ScanCursor(src.len());
         Ok(None)
     }
 
+    /// Check the limits on a line of `len` bytes that has not fully arrived yet.
+    fn partial_line_error(&self, len: usize) -> Option<Error> {
+        if exceeds(len, self.limits.line) {
+            Some(Error::LineTooLong(self.next_line))
+        } else if exceeds(len, self.limits.buffer) {
+            Some(Error::BufferTooLarge(self.next_line))
+        } else {
+            None
+        }
+    }
+
+    /// Skip a partial line of `len` bytes that exceeded a limit. The caller removes it from the
+    /// input buffer.
+    fn discard_partial_line(&mut self, len: usize, err: Error) -> Result<(), Error> {
+        self.consumed += len as u64;
+        self.last_was_cr = false;
+        // The rest of this line is discarded when it comes in.
+        self.skip_line = true;
+        if !self.skip_event {
+            self.limit_exceeded(err)?;
+        }
+        Ok(())
+    }
+
+    fn discard_trailing(&mut self, src: &mut BytesMut) -> Result<(), Error> {
+        let len = src.len();
+        src.clear();
+        self.trailing_data(len)
+    }
+
+    /// Account for `len` bytes of input that were left over at the end of the stream, and discard
+    /// the event that was not ended by a blank line.
+    fn trailing_data(&mut self, len: usize) -> Result<(), Error> {
+        self.consumed += len as u64;
+        self.scanned = ScanCursor(0);
+        self.data = DataBuffer::Empty;
+        self.event_type = None;
+        self.fields.clear();
+        let event_start = self.event_start.take();
+        if self.validation && (len > 0 || event_start.is_some()) {
+            return Err(Error::SpecViolation(
+                Violation::TrailingData,
+                event_start.unwrap_or(self.next_line),
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_event(&mut self, item: Event, dest: &mut impl EncodeBuf) -> Result<(), Error> {
+        let item = item.apply_policy(self.encode_policy)?;
+        self.write_event(&item, dest);
+        Ok(())
+    }
+
+    /// Encode an event that is shared with others, only copying it if the encode policy needs to
+    /// change it.
+    #[cfg(feature = "std")]
+    fn encode_shared_event(
+        &mut self,
+        item: &Event,
+        dest: &mut impl EncodeBuf,
+    ) -> Result<(), Error> {
+        if item.validate().is_err() {
+            return self.encode_event(item.clone(), dest);
+        }
+        self.write_event(item, dest);
+        Ok(())
+    }
+
+    /// Write an event that the encode policy has already been applied to.
+    #[cfg(feature = "std")]
+    fn write_event(&mut self, item: &Event, dest: &mut impl EncodeBuf) {
+        self.encode_preamble(dest);
+        item.encode_into(self.line_ending.as_str(), dest);
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_frame(&mut self, item: Frame, dest: &mut impl EncodeBuf) -> Result<(), Error> {
+        let item = item.apply_policy(self.encode_policy)?;
+        self.encode_preamble(dest);
+        item.encode_into(self.line_ending.as_str(), dest);
+        Ok(())
+    }
+
+    /// Write the byte order mark, padding comment and initial `retry:` field, if they are enabled
+    /// and this is the first write.
+    #[cfg(feature = "std")]
+    fn encode_preamble(&mut self, dest: &mut impl EncodeBuf) {
+        if self.wrote_preamble {
+            return;
+        }
+        self.wrote_preamble = true;
+        let eol = self.line_ending.as_str();
+        if self.bom {
+            dest.put("\u{feff}".as_bytes());
+        }
+        if self.padding > 0 {
+            const SPACES: &[u8] = &[b' '; 64];
+            dest.reserve(self.padding + 1 + eol.len());
+            dest.put(b":");
+            let mut remaining = self.padding;
+            while remaining > 0 {
+                let len = remaining.min(SPACES.len());
+                dest.put(&SPACES[..len]);
+                remaining -= len;
+            }
+            dest.put(eol.as_bytes());
+        }
+        if let Some(retry) = self.initial_retry {
+            Event::retry(retry).encode_into(eol, dest);
+        }
+    }
+
+    /// Decode from the input buffer of a framing library that uses a different version of `bytes`.
+    ///
+    /// Complete lines are moved into the codec's own buffer, so every byte is copied exactly once.
+    /// A partial line stays in `src`, so the framing library knows there is unconsumed input at the
+    /// end of the stream.
+    #[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+    fn decode_foreign(&mut self, src: &mut bytes1::BytesMut) -> Result<Option<Parsed>, Error> {
+        let scanned = self.foreign_scanned.get(src);
+        if let Some(pos) = memrchr2(b'\r', b'\n', &src[scanned..]) {
+            self.foreign_input
+                .extend_from_slice(&src.split_to(scanned + pos + 1));
+        }
+        self.foreign_scanned = ScanCursor(src.len());
+
+        let mut input = core::mem::take(&mut self.foreign_input);
+        let result = self.decode_parsed(&mut input);
+        self.foreign_input = input;
+        if let Ok(None) = result {
+            if let Some(err) = self.partial_line_error(src.len()) {
+                let len = src.len();
+                src.clear();
+                self.foreign_scanned = ScanCursor(0);
+                self.discard_partial_line(len, err)?;
+            }
+        }
+        result
+    }
+
+    /// Handle the end of the input of a framing library that uses a different version of `bytes`.
+    #[cfg(any(feature = "tokio", feature = "asynchronous-codec"))]
+    fn decode_foreign_eof(&mut self, src: &mut bytes1::BytesMut) -> Result<Option<Parsed>, Error> {
+        if let Some(parsed) = self.decode_foreign(src)? {
+            return Ok(Some(parsed));
+        }
+        let len = src.len();
+        src.clear();
+        self.foreign_scanned = ScanCursor(0);
+        self.trailing_data(len).map(|_| None)
+    }
+}
+
+#[cfg(feature = "futures_codec")]
+impl Decoder for SSECodec {
+    type Item = Event;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decode_parsed(src)?.map(Parsed::into_event))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.discard_trailing(src)?;
+        Ok(None)
+    }
+}
+
+/// Decoder for server-sent event streams that produces [`BytesEvent`]s.
+///
+/// Decoded strings share memory with the input buffer where possible, instead of being copied.
+#[derive(Debug, Default, Clone)]
+pub struct BytesDecoder {
+    codec: SSECodec,
+}
+
+impl BytesDecoder {
+    /// Create a decoder with the configuration from `codec`.
+    pub fn new(mut codec: SSECodec) -> Self {
+        codec.reset_input();
+        Self { codec }
+    }
+
+    /// Get the underlying codec.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+}
+
+impl StreamState for SSECodec {
+    fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.reconnection_time
+    }
+}
+
+impl StreamState for BytesDecoder {
+    fn last_event_id(&self) -> Option<&str> {
+        self.codec.last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.codec.reconnection_time()
+    }
+}
+
+#[cfg(feature = "futures_codec")]
+impl<R: AsyncRead, D: Decoder + StreamState> StreamState for FramedRead<R, D> {
+    fn last_event_id(&self) -> Option<&str> {
+        self.decoder().last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.decoder().reconnection_time()
+    }
+}
+
+impl From<SSECodec> for BytesDecoder {
+    fn from(codec: SSECodec) -> Self {
+        Self::new(codec)
+    }
+}
+
+#[cfg(feature = "futures_codec")]
+impl Decoder for BytesDecoder {
+    type Item = BytesEvent;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.codec.decode_parsed(src)?.map(Parsed::into_bytes_event))
+    }
+
     fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let _garbage = src.split_to(src.len());
+        self.codec.discard_trailing(src)?;
         Ok(None)
     }
 }
 
+#[cfg(feature = "futures_codec")]
 impl Encoder for SSECodec {
     type Item = Event;
     type Error = Error;
 
     fn encode(&mut self, item: Self::Item, dest: &mut BytesMut) -> Result<(), Self::Error> {
-        writeln!(dest, "{}", item).map_err(Into::into)
+        self.encode_event(item, dest)
     }
 }
 
+/// Access to the state that an event stream keeps between messages.
+///
+/// This is implemented for the decoders and for decoding streams, so the state can be carried over
+/// to a new connection, using [`SSECodec::with_last_event_id`] and
+/// [`SSECodec::with_reconnection_time`].
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{Parser, StreamState};
+///
+/// # fn main() -> Result<(), sse_codec::Error> {
+/// let mut parser = Parser::new();
+/// parser.feed(b"retry:1000\nid: 1\ndata: hello\n\n");
+/// while let Some(_) = parser.next_event()? {}
+/// assert_eq!(parser.last_event_id(), Some("1"));
+/// assert_eq!(parser.reconnection_time(), Some(1000));
+/// # Ok(()) }
+/// ```
+pub trait StreamState {
+    /// The _last event ID_: the most recent ID that was set by an `id:` field.
+    ///
+    /// See also the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#concept-event-stream-last-event-id).
+    fn last_event_id(&self) -> Option<&str>;
+
+    /// The _reconnection time_ in milliseconds: the most recent value set by a `retry:` field.
+    ///
+    /// See also the [Server-Sent Events spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#concept-event-stream-reconnection-time).
+    fn reconnection_time(&self) -> Option<u64>;
+}
+
 /// Type of a decoding stream, returned from `decode_stream()`.
+#[cfg(feature = "futures_codec")]
 pub type DecodeStream<R> = FramedRead<R, SSECodec>;
 
+/// Type of a decoding stream, returned from `decode_bytes_stream()`.
+#[cfg(feature = "futures_codec")]
+pub type BytesDecodeStream<R> = FramedRead<R, BytesDecoder>;
+
 /// Type of an encoding stream, returned from `encode_stream()`.
+#[cfg(feature = "futures_codec")]
 pub type EncodeStream<W> = FramedWrite<W, SSECodec>;
 
+/// Type of an encoding stream for `Frame`s, returned from `encode_frame_stream()`.
+#[cfg(feature = "futures_codec")]
+pub type FrameEncodeStream<W> = FramedWrite<W, FrameEncoder>;
+
+/// Type of an encoding stream that assigns event IDs, returned from `encode_auto_id_stream()`.
+#[cfg(feature = "futures_codec")]
+pub type AutoIdEncodeStream<W, G> = FramedWrite<W, AutoId<G>>;
+
 /// Parse messages from an `AsyncRead`, returning a stream of `Event`s.
+#[cfg(feature = "futures_codec")]
 pub fn decode_stream<R: AsyncRead>(input: R) -> DecodeStream<R> {
-    FramedRead::new(input, SSECodec::default())
+    decode_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from an `AsyncRead` using a configured codec, returning a stream of `Event`s.
+///
+/// The codec can be accessed while decoding using `DecodeStream::decoder()`.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{decode_stream_with, SSECodec, Utf8Mode};
+///
+/// let input: &[u8] = b"data: \xFF\n\n";
+/// let events = decode_stream_with(input, SSECodec::new().with_utf8_mode(Utf8Mode::Lossy));
+/// ```
+#[cfg(feature = "futures_codec")]
+pub fn decode_stream_with<R: AsyncRead>(input: R, mut codec: SSECodec) -> DecodeStream<R> {
+    codec.reset_input();
+    FramedRead::new(input, codec)
+}
+
+/// Parse messages from an `AsyncRead`, returning a stream of `BytesEvent`s.
+///
+/// This avoids copying event data where possible.
+#[cfg(feature = "futures_codec")]
+pub fn decode_bytes_stream<R: AsyncRead>(input: R) -> BytesDecodeStream<R> {
+    decode_bytes_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from an `AsyncRead` using a configured codec, returning a stream of
+/// `BytesEvent`s.
+#[cfg(feature = "futures_codec")]
+pub fn decode_bytes_stream_with<R: AsyncRead>(input: R, codec: SSECodec) -> BytesDecodeStream<R> {
+    FramedRead::new(input, BytesDecoder::new(codec))
 }
 
 /// Encode `Event`s into an `AsyncWrite`.
+#[cfg(feature = "futures_codec")]
 pub fn encode_stream<W: AsyncWrite>(output: W) -> EncodeStream<W> {
-    FramedWrite::new(output, SSECodec::default())
+    encode_stream_with(output, SSECodec::default())
+}
+
+/// Encode `Event`s into an `AsyncWrite` using a configured codec.
+///
+/// See [`SSECodec::with_padding`] for an example.
+#[cfg(feature = "futures_codec")]
+pub fn encode_stream_with<W: AsyncWrite>(output: W, codec: SSECodec) -> EncodeStream<W> {
+    FramedWrite::new(output, codec)
+}
+
+/// Encode `Frame`s into an `AsyncWrite`.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{encode_frame_stream, Frame};
+/// use futures::sink::SinkExt;
+///
+/// # async_std::task::block_on(async {
+/// let mut output = vec![];
+/// let mut frames = encode_frame_stream(&mut output);
+/// frames.send(Frame::new().retry(1000).id("1").data("hello")).await?;
+/// drop(frames);
+/// assert_eq!(output, b"retry: 1000\nid: 1\ndata: hello\n\n");
+/// # Ok::<(), sse_codec::Error>(())
+/// # }).unwrap();
+/// ```
+#[cfg(feature = "futures_codec")]
+pub fn encode_frame_stream<W: AsyncWrite>(output: W) -> FrameEncodeStream<W> {
+    FramedWrite::new(output, FrameEncoder::default())
+}
+
+/// Encode `Event`s into an `AsyncWrite`, assigning IDs to messages that do not have one.
+///
+/// See [`AutoId`] for an example.
+#[cfg(feature = "futures_codec")]
+pub fn encode_auto_id_stream<W: AsyncWrite, G: IdGenerator>(
+    output: W,
+    encoder: AutoId<G>,
+) -> AutoIdEncodeStream<W, G> {
+    FramedWrite::new(output, encoder)
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    /// The serialized format of `Event` must not change, because it may be persisted.
+    #[test]
+    fn event_format() {
+        let events = vec![
+            (
+                Event::Message {
+                    id: Some("1".to_string()),
+                    event: "add".to_string(),
+                    data: "a\nb".to_string(),
+                    fields: vec![("trace".to_string(), "1234".to_string())],
+                },
+                r#"{"type":"message","id":"1","event":"add","data":"a\nb","fields":[["trace","1234"]]}"#,
+            ),
+            (
+                Event::message("message", "", None),
+                r#"{"type":"message","id":null,"event":"message","data":"","fields":[]}"#,
+            ),
+            (Event::retry(3000), r#"{"type":"retry","retry":3000}"#),
+            (
+                Event::comment("keep-alive"),
+                r#"{"type":"comment","comment":"keep-alive"}"#,
+            ),
+        ];
+        for (event, json) in events {
+            assert_eq!(serde_json::to_string(&event).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Event>(json).unwrap(), event);
+        }
+
+        let minimal = r#"{"type":"message","event":"add","data":"x"}"#;
+        assert_eq!(
+            serde_json::from_str::<Event>(minimal).unwrap(),
+            Event::message("add", "x", None)
+        );
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "futures_codec"))]
 mod encode_tests {
     use super::*;
     use futures::SinkExt;
@@ -325,6 +2189,7 @@ mod encode_tests {
                 id: None,
                 event: "add".to_string(),
                 data: "test\ntest2".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
@@ -340,6 +2205,7 @@ mod encode_tests {
                 id: Some("whatever".to_string()),
                 event: "add".to_string(),
                 data: "test".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
@@ -355,12 +2221,259 @@ mod encode_tests {
                 id: None,
                 event: "message".to_string(),
                 data: "test".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
         assert_eq!(output, b"data: test\n\n".to_vec());
     }
 
+    #[async_std::test]
+    async fn custom_fields() {
+        let mut output = vec![];
+        let mut stream = encode_stream(&mut output);
+        stream
+            .send(Event::Message {
+                id: Some("1".to_string()),
+                event: "add".to_string(),
+                data: "test".to_string(),
+                fields: vec![
+                    ("trace".to_string(), "abc".to_string()),
+                    ("flag".to_string(), "".to_string()),
+                ],
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            b"id: 1\nevent: add\ntrace: abc\nflag\ndata: test\n\n".to_vec()
+        );
+    }
+
+    #[async_std::test]
+    async fn comment() {
+        let mut output = vec![];
+        let mut stream = encode_stream(&mut output);
+        stream.send(Event::comment("keep-alive")).await.unwrap();
+        stream.send(Event::comment("")).await.unwrap();
+        stream.send(Event::comment("a\nb")).await.unwrap();
+        drop(stream);
+        assert_eq!(output, b": keep-alive\n\n:\n\n: a\n: b\n\n".to_vec());
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = vec![
+            Event::message("message", "", None),
+            Event::message("message", "trailing\n", None),
+            Event::message("message", "\n\nblank lines\n\n", None),
+            Event::message("", " leading space", ""),
+            Event::Message {
+                id: Some(":".to_string()),
+                event: "add".to_string(),
+                data: "a\nb".to_string(),
+                fields: vec![
+                    ("flag".to_string(), "".to_string()),
+                    ("trace".to_string(), " x:y".to_string()),
+                ],
+            },
+            Event::comment(""),
+            Event::comment(" two spaces"),
+            Event::retry(0),
+        ];
+        for event in events {
+            let mut encoded = BytesMut::new();
+            SSECodec::new().encode(event.clone(), &mut encoded).unwrap();
+            let codec = SSECodec::new().with_comments(true).with_custom_fields(true);
+            let mut parser = Parser::with_codec(codec);
+            parser.feed(&encoded);
+            let decoded = parser.next_event().unwrap().unwrap();
+            assert_eq!(decoded, event);
+            assert_eq!(parser.next_event().unwrap(), None);
+            assert_eq!(parser.buffered_len(), 0);
+
+            let mut reencoded = BytesMut::new();
+            SSECodec::new().encode(decoded, &mut reencoded).unwrap();
+            assert_eq!(reencoded, encoded);
+        }
+    }
+
+    #[test]
+    fn encoded_len() {
+        let events = vec![
+            Event::message("add", "a\nb\nc\n", "1"),
+            Event::message("message", "", None),
+            Event::comment("a"),
+            Event::retry(0),
+            Event::retry(u64::MAX),
+        ];
+        for event in events {
+            let mut encoded = BytesMut::new();
+            SSECodec::new().encode(event.clone(), &mut encoded).unwrap();
+            assert_eq!(event.encoded_len(), encoded.len());
+            assert_eq!(encoded, format!("{}\n", event).as_bytes());
+        }
+    }
+
+    #[test]
+    fn line_endings() {
+        let event = Event::Message {
+            id: Some("1".to_string()),
+            event: "add".to_string(),
+            data: "a\nb".to_string(),
+            fields: vec![("flag".to_string(), "".to_string())],
+        };
+        for line_ending in [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr] {
+            let mut codec = SSECodec::new().with_line_ending(line_ending);
+            let mut encoded = BytesMut::new();
+            codec.encode(event.clone(), &mut encoded).unwrap();
+            codec.encode(Event::comment("x"), &mut encoded).unwrap();
+            let expected = "id: 1\nevent: add\nflag\ndata: a\ndata: b\n\n: x\n\n";
+            assert_eq!(
+                encoded,
+                expected.replace('\n', line_ending.as_str()).as_bytes()
+            );
+
+            let codec = SSECodec::new().with_comments(true).with_custom_fields(true);
+            let mut parser = Parser::with_codec(codec);
+            parser.feed(&encoded);
+            assert_eq!(parser.next_event().unwrap(), Some(event.clone()));
+            assert_eq!(parser.next_event().unwrap(), Some(Event::comment("x")));
+        }
+    }
+
+    #[test]
+    fn bom() {
+        let mut codec = SSECodec::new().with_bom(true);
+        let mut encoded = BytesMut::new();
+        assert!(codec
+            .encode(Event::message("a\nb", "", None), &mut encoded)
+            .is_err());
+        codec.encode(Event::retry(1), &mut encoded).unwrap();
+        codec.encode(Event::retry(2), &mut encoded).unwrap();
+        assert_eq!(encoded, "\u{feff}retry: 1\n\nretry: 2\n\n".as_bytes());
+    }
+
+    #[async_std::test]
+    async fn preamble() {
+        let codec = SSECodec::new()
+            .with_line_ending(LineEnding::CrLf)
+            .with_bom(true)
+            .with_padding(100)
+            .with_initial_retry(10);
+        let mut output = vec![];
+        let mut stream = encode_stream_with(&mut output, codec);
+        stream.send(Event::comment("a")).await.unwrap();
+        stream.send(Event::comment("b")).await.unwrap();
+        drop(stream);
+        let mut expected = "\u{feff}:".to_string();
+        expected.push_str(&" ".repeat(100));
+        expected.push_str("\r\nretry: 10\r\n\r\n: a\r\n\r\n: b\r\n\r\n");
+        assert_eq!(output, expected.as_bytes());
+
+        let mut parser = Parser::with_codec(SSECodec::new().with_comments(true));
+        parser.feed(&output);
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(Event::comment(&" ".repeat(99)))
+        );
+        assert_eq!(parser.next_event().unwrap(), Some(Event::retry(10)));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::comment("a")));
+    }
+
+    #[test]
+    fn carriage_return_is_line_break() {
+        let event = Event::message("message", "a\rb\r\nc\r", None);
+        assert_eq!(event.to_string(), "data: a\ndata: b\ndata: c\ndata\n");
+        let comment = Event::comment("a\rb");
+        assert_eq!(comment.to_string(), ": a\n: b\n");
+
+        let mut encoded = BytesMut::new();
+        let mut codec = SSECodec::new();
+        codec.encode(event, &mut encoded).unwrap();
+        codec.encode(comment, &mut encoded).unwrap();
+        assert_eq!(
+            &encoded[..],
+            b"data: a\ndata: b\ndata: c\ndata\n\n: a\n: b\n\n"
+        );
+        let mut parser = Parser::with_codec(SSECodec::new().with_comments(true));
+        parser.feed(&encoded);
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(Event::message("message", "a\nb\nc\n", None))
+        );
+        assert_eq!(parser.next_event().unwrap(), Some(Event::comment("a")));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::comment("b")));
+        assert_eq!(
+            "data: a\rdata: b\r\n\r\n".parse::<Event>().unwrap(),
+            Event::message("message", "a\nb", None)
+        );
+    }
+
+    fn unsafe_message() -> Event {
+        Event::Message {
+            id: Some("1\0\n".to_string()),
+            event: "add\r\ndata: injected".to_string(),
+            data: "test".to_string(),
+            fields: vec![
+                ("trace".to_string(), "a\nb".to_string()),
+                ("da:ta".to_string(), "1".to_string()),
+                ("data".to_string(), "injected".to_string()),
+                ("".to_string(), "comment".to_string()),
+            ],
+        }
+    }
+
+    #[async_std::test]
+    async fn encode_policy_reject() {
+        let mut output = vec![];
+        let mut stream = encode_stream(&mut output);
+        assert!(matches!(
+            stream.send(unsafe_message()).await,
+            Err(Error::InvalidField(field)) if field == "id"
+        ));
+        assert!(matches!(
+            Event::try_message("add", "test", "1\0"),
+            Err(Error::InvalidField(field)) if field == "id"
+        ));
+        let mut message = Event::message("add", "test", None);
+        assert!(message.validate().is_ok());
+        if let Event::Message { fields, .. } = &mut message {
+            fields.push(("retry".to_string(), "1".to_string()));
+        }
+        assert!(matches!(
+            message.validate(),
+            Err(Error::InvalidField(field)) if field == "retry"
+        ));
+        drop(stream);
+        assert!(output.is_empty());
+    }
+
+    #[async_std::test]
+    async fn encode_policy_strip() {
+        let mut output = vec![];
+        let codec = SSECodec::new().with_encode_policy(EncodePolicy::Strip);
+        let mut stream = FramedWrite::new(&mut output, codec);
+        stream.send(unsafe_message()).await.unwrap();
+        assert_eq!(
+            output,
+            b"id: 1\nevent: adddata: injected\ntrace: ab\ndata: test\n\n".to_vec()
+        );
+    }
+
+    #[async_std::test]
+    async fn encode_policy_replace() {
+        let mut output = vec![];
+        let codec = SSECodec::new().with_encode_policy(EncodePolicy::Replace);
+        let mut stream = FramedWrite::new(&mut output, codec);
+        stream.send(unsafe_message()).await.unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id: 1\u{fffd}\u{fffd}\nevent: add\u{fffd}\u{fffd}data: injected\n\
+             trace: a\u{fffd}b\nda\u{fffd}ta: 1\ndata: test\n\n"
+        );
+    }
+
     #[async_std::test]
     async fn multiple_events() {
         let mut output = vec![];
@@ -370,6 +2483,7 @@ mod encode_tests {
                 id: None,
                 event: "add".to_string(),
                 data: "test\ntest2".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
@@ -378,6 +2492,7 @@ mod encode_tests {
                 id: Some("whatever".to_string()),
                 event: "add".to_string(),
                 data: "test".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
@@ -386,10 +2501,11 @@ mod encode_tests {
                 id: None,
                 event: "message".to_string(),
                 data: "test".to_string(),
+                fields: vec![],
             })
             .await
             .unwrap();
-        let mut expected = vec![];
+        let mut expected: Vec<u8> = vec![];
         expected.extend(b"event: add\ndata: test\ndata: test2\n\n".iter());
         expected.extend(b"id: whatever\nevent: add\ndata: test\n\n".iter());
         expected.extend(b"data: test\n\n".iter());
@@ -397,7 +2513,7 @@ mod encode_tests {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "futures_codec"))]
 mod decode_tests {
     use super::*;
     use futures::stream::{self, StreamExt, TryStreamExt};
@@ -408,8 +2524,8 @@ mod decode_tests {
         let mut event = None;
         let s = "event: add\ndata: test\ndata: test2\n\n";
         for line in s.lines() {
-            if let Some(message @ Event::Message { .. }) = codec.parse_line(line) {
-                event = Some(message);
+            if let Some(message @ Parsed::Message { .. }) = codec.parse_line(line.into()).unwrap() {
+                event = Some(message.into_event());
                 break;
             }
         }
@@ -419,6 +2535,7 @@ mod decode_tests {
                 id: None,
                 event: "add".to_string(),
                 data: "test\ntest2".to_string(),
+                fields: vec![],
             })
         );
     }
@@ -439,7 +2556,7 @@ mod decode_tests {
         let results = result.unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(
-            results.get(0).unwrap(),
+            results.first().unwrap(),
             &Event::message("message", "data1", "id1")
         );
     }
@@ -469,6 +2586,470 @@ mod decode_tests {
         );
     }
 
+    fn decode_all_with(input: &'static [u8], codec: SSECodec) -> (Vec<Result<Event, Error>>, u64) {
+        let mut messages = decode_stream_with(input, codec);
+        let mut results = vec![];
+        async_std::task::block_on(async {
+            while let Some(result) = messages.next().await {
+                let is_err = result.is_err();
+                results.push(result);
+                if is_err {
+                    break;
+                }
+            }
+        });
+        (results, messages.decoder().utf8_errors())
+    }
+
+    #[test]
+    fn utf8_strict() {
+        let (results, _) = decode_all_with(b"data:ok\xFF\n\ndata:2\n\n", SSECodec::new());
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(Error::Utf8Error(..))));
+    }
+
+    #[test]
+    fn utf8_lossy() {
+        let codec = SSECodec::new().with_utf8_mode(Utf8Mode::Lossy);
+        let (results, errors) =
+            decode_all_with(b"data:a\xFFb\xE2\x80\ndata:c\xF0\x9F\x92\xA9\n\n", codec);
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            results,
+            vec![Event::message(
+                "message",
+                "a\u{fffd}b\u{fffd}\nc\u{1f4a9}",
+                None
+            )]
+        );
+        assert_eq!(errors, 2);
+    }
+
+    #[test]
+    fn utf8_skip_line() {
+        let codec = SSECodec::new().with_utf8_mode(Utf8Mode::SkipLine);
+        let (results, errors) = decode_all_with(
+            b"data:1\ndata:\xFF\xFF\ndata:2\n\nevent:\xC0\ndata:3\n\n",
+            codec,
+        );
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            results,
+            vec![
+                Event::message("message", "1\n2", None),
+                Event::message("message", "3", None),
+            ]
+        );
+        assert_eq!(errors, 3);
+    }
+
+    /// Feed `chunks` to the decoder one by one, continuing after errors.
+    fn decode_chunks(codec: &mut SSECodec, chunks: &[&[u8]]) -> Vec<Result<Event, Error>> {
+        let mut buffer = BytesMut::new();
+        let mut results = vec![];
+        for chunk in chunks {
+            buffer.extend_from_slice(chunk);
+            loop {
+                match codec.decode(&mut buffer) {
+                    Ok(Some(event)) => results.push(Ok(event)),
+                    Ok(None) => break,
+                    Err(err) => results.push(Err(err)),
+                }
+            }
+        }
+        results
+    }
+
+    #[test]
+    fn decode_line_in_chunks() {
+        let mut codec = SSECodec::new();
+        let results = decode_chunks(
+            &mut codec,
+            &[
+                b"da",
+                b"ta:hel",
+                b"lo",
+                b"\r",
+                b"\ndata",
+                b":",
+                b"world\r\n",
+                b"\r\n",
+            ],
+        );
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            results,
+            vec![Event::message("message", "hello\nworld", None)]
+        );
+    }
+
+    #[test]
+    fn limit_line_length() {
+        let mut codec = SSECodec::new().with_limits(Limits::new().max_line_length(10));
+        let results = decode_chunks(&mut codec, &[b"data:12345678\ndata:x\n\ndata:1234\n\n"]);
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(Error::LineTooLong(_))));
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &Event::message("message", "1234", None)
+        );
+    }
+
+    #[test]
+    fn limit_partial_line_length() {
+        let mut codec = SSECodec::new().with_limits(Limits::new().max_line_length(8));
+        let results = decode_chunks(
+            &mut codec,
+            &[b"data:1234", b"5678", b"9\n", b"\n", b"data:1\n\n"],
+        );
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(Error::LineTooLong(_))));
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &Event::message("message", "1", None)
+        );
+    }
+
+    #[test]
+    fn limit_buffer_size() {
+        let mut codec = SSECodec::new().with_limits(Limits::new().max_buffer_size(4));
+        let mut buffer = BytesMut::from(&b"data:1\n\ndata:2"[..]);
+        assert_eq!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Event::message("message", "1", None))
+        );
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(Error::BufferTooLarge(_))
+        ));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn limit_fields() {
+        let limits = Limits::new()
+            .max_data_length(8)
+            .max_event_type_length(3)
+            .max_id_length(3);
+        let mut codec = SSECodec::new().with_limits(limits);
+        let results = decode_chunks(
+            &mut codec,
+            &[
+                b"data:1234\ndata:5678\n\n",
+                b"event:long\ndata:x\n\n",
+                b"id:long\ndata:x\n\n",
+                b"id:1\nevent:ok\ndata:12\ndata:567\n\n",
+            ],
+        );
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[0], Err(Error::DataTooLong(_))));
+        assert!(matches!(results[1], Err(Error::EventTypeTooLong(_))));
+        assert!(matches!(results[2], Err(Error::IdTooLong(_))));
+        assert_eq!(
+            results[3].as_ref().unwrap(),
+            &Event::message("ok", "12\n567", "1")
+        );
+    }
+
+    #[test]
+    fn limit_data_length_exact() {
+        let mut codec = SSECodec::new().with_limits(Limits::new().max_data_length(4));
+        let results = decode_chunks(
+            &mut codec,
+            &[
+                b"data:1234\n\n",
+                b"data:12\ndata:3\n\n",
+                b"data:12\ndata:34\n\n",
+            ],
+        );
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &Event::message("message", "1234", None)
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &Event::message("message", "12\n3", None)
+        );
+        assert!(matches!(results[2], Err(Error::DataTooLong(_))));
+    }
+
+    #[test]
+    fn limit_discard() {
+        let limits = Limits::new()
+            .max_line_length(8)
+            .max_data_length(8)
+            .on_exceeded(LimitAction::Discard);
+        let mut codec = SSECodec::new().with_limits(limits);
+        let results = decode_chunks(
+            &mut codec,
+            &[
+                b"data:1234\ndata:5678\n\n",
+                b"data:1\n\n",
+                b"data:123456789",
+                b"\n\ndata:2\n\n",
+            ],
+        );
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            results,
+            vec![
+                Event::message("message", "1", None),
+                Event::message("message", "2", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn bytes_decoder_shares_input() {
+        let mut decoder = BytesDecoder::default();
+        let mut buffer = BytesMut::from(&b"id: 1\nevent: add\ndata: payload\n\n"[..]);
+        let input = buffer.as_ptr() as usize..buffer.as_ptr() as usize + buffer.len();
+        let event = decoder.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(
+            event,
+            BytesEvent::Message {
+                id: Some("1".into()),
+                event: "add".into(),
+                data: "payload".into(),
+                fields: vec![],
+            }
+        );
+        if let BytesEvent::Message { event, data, .. } = event {
+            assert!(input.contains(&(event.as_ptr() as usize)));
+            assert!(input.contains(&(data.as_ptr() as usize)));
+        }
+    }
+
+    #[test]
+    fn last_event_id_does_not_share_input() {
+        let mut decoder = BytesDecoder::default();
+        let mut buffer = BytesMut::from(&b"id: 1\ndata: payload\n\n"[..]);
+        let input = buffer.as_ptr() as usize..buffer.as_ptr() as usize + buffer.len();
+        let event = decoder.decode(&mut buffer).unwrap().unwrap();
+        drop(buffer);
+        let id = decoder.last_event_id().unwrap();
+        assert_eq!(id, "1");
+        assert!(!input.contains(&(id.as_ptr() as usize)));
+        if let BytesEvent::Message { id, .. } = event {
+            assert!(!input.contains(&(id.unwrap().as_ptr() as usize)));
+        }
+    }
+
+    #[test]
+    fn bytes_decoder_joins_lines() {
+        let input: &[u8] = b"data:1\ndata:2\n\nretry:100\n";
+        let events = decode_bytes_stream(input);
+        let events: Vec<_> = async_std::task::block_on(events.map(Result::unwrap).collect());
+        assert_eq!(
+            events,
+            vec![
+                BytesEvent::Message {
+                    id: None,
+                    event: "message".into(),
+                    data: "1\n2".into(),
+                    fields: vec![],
+                },
+                BytesEvent::Retry { retry: 100 },
+            ]
+        );
+        assert_eq!(
+            Event::from(events[0].clone()),
+            Event::message("message", "1\n2", None)
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let input: &[u8] = b": hello\ndata:1\n:\n:ping 1234\n\n";
+        let events = decode_stream_with(input, SSECodec::new().with_comments(true));
+        let events: Vec<_> = async_std::task::block_on(events.map(Result::unwrap).collect());
+        assert_eq!(
+            events,
+            vec![
+                Event::comment("hello"),
+                Event::comment(""),
+                Event::comment("ping 1234"),
+                Event::message("message", "1", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_fields() {
+        let input: &[u8] = b"trace: abc\ndata:1\nts:1234\nflag\nretry:x\n\ndata:2\n\n";
+        let events = decode_stream_with(input, SSECodec::new().with_custom_fields(true));
+        let events: Vec<_> = async_std::task::block_on(events.map(Result::unwrap).collect());
+        assert_eq!(
+            events,
+            vec![
+                Event::Message {
+                    id: None,
+                    event: "message".into(),
+                    data: "1".into(),
+                    fields: vec![
+                        ("trace".into(), "abc".into()),
+                        ("ts".into(), "1234".into()),
+                        ("flag".into(), "".into()),
+                    ],
+                },
+                Event::message("message", "2", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn limit_custom_fields() {
+        let mut codec = SSECodec::new()
+            .with_custom_fields(true)
+            .with_limits(Limits::new().max_fields(1));
+        let results = decode_chunks(&mut codec, &[b"a:1\nb:2\ndata:x\n\ndata:y\n\n"]);
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(Error::TooManyFields(_))));
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &Event::message("message", "y", None)
+        );
+    }
+
+    #[test]
+    fn restore_state() {
+        let input: &[u8] = b"retry:2000\nid:5\ndata:1\n\n";
+        let mut events = decode_stream(input);
+        async_std::task::block_on(async { while events.next().await.is_some() {} });
+        assert_eq!(events.last_event_id(), Some("5"));
+        assert_eq!(events.reconnection_time(), Some(2000));
+
+        let codec = SSECodec::new()
+            .with_last_event_id(events.last_event_id().unwrap())
+            .with_reconnection_time(events.reconnection_time().unwrap());
+        let input: &[u8] = b"data:2\n\nid:6\ndata:3\n\n";
+        let mut events = decode_bytes_stream_with(input, codec);
+        let first = async_std::task::block_on(events.next()).unwrap().unwrap();
+        assert_eq!(Event::from(first), Event::message("message", "2", "5"));
+        assert_eq!(events.reconnection_time(), Some(2000));
+        async_std::task::block_on(async { while events.next().await.is_some() {} });
+        assert_eq!(events.last_event_id(), Some("6"));
+    }
+
+    #[test]
+    fn retry_leading_space() {
+        let input: &[u8] = b"retry: 3000\nretry:  1000\ndata:x\n\n";
+        let events = decode_stream(input);
+        let events: Vec<_> = async_std::task::block_on(events.map(Result::unwrap).collect());
+        assert_eq!(
+            events,
+            vec![Event::retry(3000), Event::message("message", "x", None)]
+        );
+    }
+
+    #[test]
+    fn retry_state() {
+        let input: &[u8] = b"data:1\nretry:3000\ndata:2\n\nretry:x\n\n";
+        let mut events =
+            decode_stream_with(input, SSECodec::new().with_retry_mode(RetryMode::State));
+        let mut results = vec![];
+        async_std::task::block_on(async {
+            while let Some(event) = events.next().await {
+                results.push(event.unwrap());
+            }
+        });
+        assert_eq!(results, vec![Event::message("message", "1\n2", None)]);
+        assert_eq!(events.reconnection_time(), Some(3000));
+    }
+
+    #[test]
+    fn error_positions() {
+        let mut codec = SSECodec::new().with_limits(Limits::new().max_line_length(8));
+        let results = decode_chunks(&mut codec, &[b"data:1\r\n\r\ndata:123456\n\ndata:\xFF\n"]);
+        let positions: Vec<_> = results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .map(|err| err.position().unwrap())
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                Position {
+                    line: 3,
+                    offset: 10
+                },
+                Position {
+                    line: 5,
+                    offset: 23
+                },
+            ]
+        );
+        assert!(matches!(results[1], Err(Error::LineTooLong(_))));
+        assert!(matches!(results[2], Err(Error::Utf8Error(..))));
+
+        assert!(matches!(
+            "data:1\n".parse::<Event>(),
+            Err(Error::IncompleteFrame(Position { line: 2, offset: 7 }))
+        ));
+    }
+
+    #[test]
+    fn validation() {
+        let mut codec = SSECodec::new().with_validation(true);
+        let results = decode_chunks(
+            &mut codec,
+            &[
+                b"\xEF\xBB\xBFdata:1\n",
+                b"foo:bar\n",
+                b"retry:1s\n",
+                b"id:a\0b\n",
+                b"\xEF\xBB\xBFdata:2\n",
+                b"\n",
+            ],
+        );
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|result| match result {
+                Err(Error::SpecViolation(violation, position)) => Err((violation, position.line)),
+                Err(err) => panic!("unexpected error {}", err),
+                Ok(event) => Ok(event),
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Err((Violation::UnknownField("foo".into()), 2)),
+                Err((Violation::InvalidRetry("1s".into()), 3)),
+                Err((Violation::NullInId, 4)),
+                Err((Violation::MisplacedBom, 5)),
+                Ok(Event::message("message", "1", None)),
+            ]
+        );
+
+        let mut buffer = BytesMut::from(&b"data:1\n\ndata:2"[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_some());
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(matches!(
+            codec.decode_eof(&mut buffer),
+            Err(Error::SpecViolation(Violation::TrailingData, _))
+        ));
+    }
+
+    /// `FramedRead` does not call `decode_eof` once its buffer is empty, so trailing data made of
+    /// complete lines is not reported by `decode_stream_with`.
+    #[test]
+    fn validation_trailing_complete_lines() {
+        let input: &[u8] = b"data: 1\n\ndata: 2\n";
+        let events = decode_stream_with(input, SSECodec::new().with_validation(true));
+        let results: Vec<_> = async_std::task::block_on(events.collect());
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+    }
+
+    #[test]
+    fn bom_only_at_start() {
+        let input: &[u8] = b"data:1\n\xEF\xBB\xBFdata:2\n\n";
+        let events = decode_stream(input);
+        let events: Vec<_> = async_std::task::block_on(events.map(Result::unwrap).collect());
+        assert_eq!(events, vec![Event::message("message", "1", None)]);
+    }
+
     /// Regression test: `id:` lines had historically immediately emitted an event message in
     /// sse-codec, but shouldn't.
     #[test]
@@ -504,7 +3085,7 @@ mod decode_tests {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "futures_codec"))]
 mod wpt {
     //! EventSource tests from the web-platform-tests suite. See https://github.com/web-platform-tests/wpt/tree/master/eventsource
 
@@ -555,7 +3136,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "msg\nmsg".into()
+                data: "msg\nmsg".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -563,7 +3145,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "".into()
+                data: "".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -571,7 +3154,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "end".into()
+                data: "end".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -596,7 +3180,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "1".into()
+                data: "1".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -604,7 +3189,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "3".into()
+                data: "3".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -630,7 +3216,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "2".into()
+                data: "2".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -638,7 +3225,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "3".into()
+                data: "3".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -663,7 +3251,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "1\n2\n3\n4".into()
+                data: "1\n2\n3\n4".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -683,7 +3272,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "test1".into()
+                data: "test1".into(),
+                fields: vec![],
             })
         );
         assert!(dbg!(messages.next()).is_none());
@@ -699,7 +3289,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "".into()
+                data: "".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -707,7 +3298,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "\n".into()
+                data: "\n".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -715,7 +3307,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "test".into()
+                data: "test".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -731,7 +3324,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "".into(),
-                data: "data".into()
+                data: "data".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -747,7 +3341,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "test".into(),
-                data: "x".into()
+                data: "x".into(),
+                fields: vec![],
             })
         );
         assert_eq!(
@@ -755,7 +3350,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "x".into()
+                data: "x".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -785,7 +3381,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "\0\n 2\n1\n3\n\n4".into()
+                data: "\0\n 2\n1\n3\n\n4".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -805,7 +3402,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "x".into()
+                data: "x".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -821,7 +3419,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "test".into()
+                data: "test".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -841,7 +3440,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "x".into()
+                data: "x".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -858,7 +3458,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "test\n\ntest".into()
+                data: "test\n\ntest".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -874,7 +3475,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "\ttest\n\ntest".into()
+                data: "\ttest\n\ntest".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -890,7 +3492,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "test\n\ntest".into()
+                data: "test\n\ntest".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -906,7 +3509,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "\0".into()
+                data: "\0".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
@@ -922,7 +3526,8 @@ mod wpt {
             Some(Event::Message {
                 id: None,
                 event: "message".into(),
-                data: "ok…".into()
+                data: "ok…".into(),
+                fields: vec![],
             })
         );
         assert!(messages.next().is_none());
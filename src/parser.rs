@@ -0,0 +1,203 @@
+use crate::{BytesEvent, Error, Event, Parsed, SSECodec, StreamState};
+use bytes::BytesMut;
+
+/// A push parser for server-sent event streams, independent of any IO or async runtime.
+///
+/// Input is passed in with [`Parser::feed`] in chunks of any size, and completed events are taken
+/// out with [`Parser::next_event`]. The parser buffers partial lines and handles line terminators
+/// and the byte order mark itself.
+///
+/// # Examples
+/// ```rust
+/// use sse_codec::{Event, Parser};
+///
+/// # fn main() -> Result<(), sse_codec::Error> {
+/// let mut parser = Parser::new();
+/// parser.feed(b"data: hel");
+/// assert_eq!(parser.next_event()?, None);
+/// parser.feed(b"lo\r\n\r\n");
+/// assert_eq!(parser.next_event()?, Some(Event::message("message", "hello", None)));
+/// parser.finish()?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Parser {
+    codec: SSECodec,
+    buffer: BytesMut,
+}
+
+impl Parser {
+    /// Create a parser with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser with the configuration and state from `codec`.
+    pub fn with_codec(mut codec: SSECodec) -> Self {
+        codec.reset_input();
+        Self {
+            codec,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Get the underlying codec.
+    pub fn codec(&self) -> &SSECodec {
+        &self.codec
+    }
+
+    /// The number of bytes that were fed in, but not parsed yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Add input to the parser.
+    ///
+    /// Nothing is parsed until [`Parser::next_event`] is called.
+    pub fn feed(&mut self, input: &[u8]) {
+        self.buffer.extend_from_slice(input);
+    }
+
+    /// Take the next completed event out of the parser. Returns `None` if more input is needed.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        Ok(self
+            .codec
+            .decode_parsed(&mut self.buffer)?
+            .map(Parsed::into_event))
+    }
+
+    /// Take the next completed event out of the parser as a [`BytesEvent`], which shares memory
+    /// with the input buffer where possible. Returns `None` if more input is needed.
+    pub fn next_bytes_event(&mut self) -> Result<Option<BytesEvent>, Error> {
+        Ok(self
+            .codec
+            .decode_parsed(&mut self.buffer)?
+            .map(Parsed::into_bytes_event))
+    }
+
+    /// Signal the end of the input.
+    ///
+    /// Any events that are still in the parser should be taken out with [`Parser::next_event`]
+    /// first. An incomplete event at the end of the input is discarded, as the spec requires.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        self.codec.discard_trailing(&mut self.buffer)
+    }
+}
+
+impl From<SSECodec> for Parser {
+    fn from(codec: SSECodec) -> Self {
+        Self::with_codec(codec)
+    }
+}
+
+impl StreamState for Parser {
+    fn last_event_id(&self) -> Option<&str> {
+        self.codec.last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.codec.reconnection_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Violation;
+    use alloc::vec;
+
+    #[test]
+    fn feed_bytes() {
+        let input = b"\xEF\xBB\xBFid: 1\r\nevent: add\rdata: a\r\ndata: b\n\nretry: 5\n";
+        let mut parser = Parser::new();
+        let mut events = vec![];
+        for byte in input.iter() {
+            parser.feed(&[*byte]);
+            while let Some(event) = parser.next_event().unwrap() {
+                events.push(event);
+            }
+        }
+        parser.finish().unwrap();
+        assert_eq!(
+            events,
+            vec![Event::message("add", "a\nb", "1"), Event::retry(5)]
+        );
+        assert_eq!(parser.last_event_id(), Some("1"));
+        assert_eq!(parser.buffered_len(), 0);
+    }
+
+    #[test]
+    fn bytes_events() {
+        let mut parser = Parser::new();
+        parser.feed(b"data: hello\n\n");
+        let event = parser.next_bytes_event().unwrap().unwrap();
+        assert_eq!(event, Event::message("message", "hello", None).into());
+        assert_eq!(parser.next_bytes_event().unwrap(), None);
+    }
+
+    #[test]
+    fn finish_discards_incomplete_event() {
+        let mut parser = Parser::new();
+        parser.feed(b"data: incomplete\n");
+        assert_eq!(parser.next_event().unwrap(), None);
+        parser.finish().unwrap();
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        let mut parser = Parser::with_codec(SSECodec::new().with_validation(true));
+        parser.feed(b"data: 1\n\ndata: incomplete");
+        assert!(parser.next_event().unwrap().is_some());
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert!(matches!(
+            parser.finish(),
+            Err(Error::SpecViolation(Violation::TrailingData, _))
+        ));
+    }
+
+    #[test]
+    fn finish_reports_complete_lines_without_blank_line() {
+        let mut parser = Parser::with_codec(SSECodec::new().with_validation(true));
+        parser.feed(b"data: 1\n\n: comment\ndata: 2\nid: 3\n");
+        assert!(parser.next_event().unwrap().is_some());
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert!(matches!(
+            parser.finish(),
+            Err(Error::SpecViolation(Violation::TrailingData, position)) if position.line == 4
+        ));
+        // The incomplete event was discarded.
+        parser.feed(b"\n");
+        assert_eq!(parser.next_event().unwrap(), None);
+        parser.finish().unwrap();
+
+        let mut parser = Parser::with_codec(SSECodec::new().with_validation(true));
+        parser.feed(b"data: 1\n\n: comment\n");
+        assert!(parser.next_event().unwrap().is_some());
+        assert_eq!(parser.next_event().unwrap(), None);
+        parser.finish().unwrap();
+    }
+
+    #[test]
+    fn codec_with_partial_line_in_new_parser() {
+        let mut parser = Parser::new();
+        parser.feed(b"data: partial");
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        let mut moved = Parser::with_codec(parser.codec().clone());
+        moved.feed(b"x\n\ndata: y\n\n");
+        assert_eq!(
+            moved.next_event().unwrap(),
+            Some(Event::message("message", "y", None))
+        );
+
+        let mut moved = Parser::with_codec(parser.codec().clone());
+        moved.feed(b"data: a\n\ndata: b\n\n");
+        assert_eq!(
+            moved.next_event().unwrap(),
+            Some(Event::message("message", "a", None))
+        );
+        assert_eq!(
+            moved.next_event().unwrap(),
+            Some(Event::message("message", "b", None))
+        );
+        assert_eq!(moved.next_event().unwrap(), None);
+    }
+}
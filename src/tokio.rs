@@ -0,0 +1,234 @@
+//! Support for [`tokio-util`](https://crates.io/crates/tokio-util)'s codec traits, and streams over
+//! `tokio::io::AsyncRead` and `tokio::io::AsyncWrite`.
+//!
+//! Requires the `tokio` feature.
+//!
+//! # Examples
+//! ```rust
+//! use sse_codec::{tokio::decode_stream, Event};
+//! use futures::stream::TryStreamExt; // for try_next()
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let input: &[u8] = b"data: hello\n\n";
+//! let mut events = decode_stream(input);
+//! assert_eq!(events.try_next().await?, Some(Event::message("message", "hello", None)));
+//! # Ok::<(), sse_codec::Error>(())
+//! # }).unwrap();
+//! ```
+use crate::{
+    AutoId, BytesDecoder, BytesEvent, Error, Event, Frame, FrameEncoder, IdGenerator, Parsed,
+    SSECodec, StreamState,
+};
+use ::tokio::io::{AsyncRead, AsyncWrite};
+use bytes1::BytesMut;
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+impl Decoder for SSECodec {
+    type Item = Event;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decode_foreign(src)?.map(Parsed::into_event))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decode_foreign_eof(src)?.map(Parsed::into_event))
+    }
+}
+
+impl Decoder for BytesDecoder {
+    type Item = BytesEvent;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .codec
+            .decode_foreign(src)?
+            .map(Parsed::into_bytes_event))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .codec
+            .decode_foreign_eof(src)?
+            .map(Parsed::into_bytes_event))
+    }
+}
+
+impl Encoder<Event> for SSECodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_event(item, dest)
+    }
+}
+
+impl Encoder<Frame> for FrameEncoder {
+    type Error = Error;
+
+    fn encode(&mut self, item: Frame, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.codec.encode_frame(item, dest)
+    }
+}
+
+impl<G: IdGenerator> Encoder<Event> for AutoId<G> {
+    type Error = Error;
+
+    fn encode(&mut self, item: Event, dest: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_event(item, dest)
+    }
+}
+
+impl<R, D: StreamState> StreamState for FramedRead<R, D> {
+    fn last_event_id(&self) -> Option<&str> {
+        self.decoder().last_event_id()
+    }
+
+    fn reconnection_time(&self) -> Option<u64> {
+        self.decoder().reconnection_time()
+    }
+}
+
+/// Type of a decoding stream, returned from `tokio::decode_stream()`.
+pub type DecodeStream<R> = FramedRead<R, SSECodec>;
+
+/// Type of a decoding stream, returned from `tokio::decode_bytes_stream()`.
+pub type BytesDecodeStream<R> = FramedRead<R, BytesDecoder>;
+
+/// Type of an encoding stream, returned from `tokio::encode_stream()`.
+pub type EncodeStream<W> = FramedWrite<W, SSECodec>;
+
+/// Type of an encoding stream for `Frame`s, returned from `tokio::encode_frame_stream()`.
+pub type FrameEncodeStream<W> = FramedWrite<W, FrameEncoder>;
+
+/// Type of an encoding stream that assigns event IDs, returned from
+/// `tokio::encode_auto_id_stream()`.
+pub type AutoIdEncodeStream<W, G> = FramedWrite<W, AutoId<G>>;
+
+/// Parse messages from a `tokio::io::AsyncRead`, returning a stream of `Event`s.
+pub fn decode_stream<R: AsyncRead>(input: R) -> DecodeStream<R> {
+    decode_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from a `tokio::io::AsyncRead` using a configured codec, returning a stream of
+/// `Event`s.
+pub fn decode_stream_with<R: AsyncRead>(input: R, mut codec: SSECodec) -> DecodeStream<R> {
+    codec.reset_input();
+    FramedRead::new(input, codec)
+}
+
+/// Parse messages from a `tokio::io::AsyncRead`, returning a stream of `BytesEvent`s.
+///
+/// Unlike the `futures_codec` version, the input is copied once into the decoder's own buffer.
+pub fn decode_bytes_stream<R: AsyncRead>(input: R) -> BytesDecodeStream<R> {
+    decode_bytes_stream_with(input, SSECodec::default())
+}
+
+/// Parse messages from a `tokio::io::AsyncRead` using a configured codec, returning a stream of
+/// `BytesEvent`s.
+pub fn decode_bytes_stream_with<R: AsyncRead>(input: R, codec: SSECodec) -> BytesDecodeStream<R> {
+    FramedRead::new(input, BytesDecoder::new(codec))
+}
+
+/// Encode `Event`s into a `tokio::io::AsyncWrite`.
+pub fn encode_stream<W: AsyncWrite>(output: W) -> EncodeStream<W> {
+    encode_stream_with(output, SSECodec::default())
+}
+
+/// Encode `Event`s into a `tokio::io::AsyncWrite` using a configured codec.
+pub fn encode_stream_with<W: AsyncWrite>(output: W, codec: SSECodec) -> EncodeStream<W> {
+    FramedWrite::new(output, codec)
+}
+
+/// Encode `Frame`s into a `tokio::io::AsyncWrite`.
+pub fn encode_frame_stream<W: AsyncWrite>(output: W) -> FrameEncodeStream<W> {
+    FramedWrite::new(output, FrameEncoder::default())
+}
+
+/// Encode `Event`s into a `tokio::io::AsyncWrite`, assigning IDs to messages that do not have one.
+pub fn encode_auto_id_stream<W: AsyncWrite, G: IdGenerator>(
+    output: W,
+    encoder: AutoId<G>,
+) -> AutoIdEncodeStream<W, G> {
+    FramedWrite::new(output, encoder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limits, Utf8Mode, Violation};
+    use futures::{SinkExt, StreamExt};
+
+    #[::tokio::test]
+    async fn decode() {
+        let input: &[u8] = b"retry: 10\nid: 1\ndata: a\ndata: b\n\ndata: trailing";
+        let mut events = decode_stream(input);
+        assert_eq!(events.next().await.unwrap().unwrap(), Event::retry(10));
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            Event::message("message", "a\nb", "1")
+        );
+        assert!(events.next().await.is_none());
+        assert_eq!(events.last_event_id(), Some("1"));
+        assert_eq!(events.reconnection_time(), Some(10));
+    }
+
+    #[::tokio::test]
+    async fn decode_bytes() {
+        let input: &[u8] = b"event: add\ndata: 1\n\n";
+        let mut events = decode_bytes_stream(input);
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            BytesEvent::from(Event::message("add", "1", None))
+        );
+        assert!(events.next().await.is_none());
+    }
+
+    #[::tokio::test]
+    async fn decode_trailing_data() {
+        let input: &[u8] = b"data: 1\n\ndata: 2\n";
+        let mut events = decode_stream_with(input, SSECodec::new().with_validation(true));
+        assert!(events.next().await.unwrap().is_ok());
+        assert!(matches!(
+            events.next().await,
+            Some(Err(Error::SpecViolation(Violation::TrailingData, _)))
+        ));
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn decode_in_chunks() {
+        let mut codec = SSECodec::new()
+            .with_utf8_mode(Utf8Mode::Lossy)
+            .with_limits(Limits::new().max_line_length(10));
+        let mut buffer = BytesMut::new();
+        buffer.extend_from_slice(b"data: \xFF");
+        assert_eq!(Decoder::decode(&mut codec, &mut buffer).unwrap(), None);
+        assert_eq!(buffer.len(), 7);
+        buffer.extend_from_slice(b"\n\ndata: 0123456789\n\n");
+        assert_eq!(
+            Decoder::decode(&mut codec, &mut buffer).unwrap(),
+            Some(Event::message("message", "\u{fffd}", None))
+        );
+        assert!(matches!(
+            Decoder::decode(&mut codec, &mut buffer),
+            Err(Error::LineTooLong(_))
+        ));
+    }
+
+    #[::tokio::test]
+    async fn encode() {
+        let mut output = vec![];
+        let mut stream = encode_stream(&mut output);
+        stream
+            .send(Event::message("add", "a\nb", "1"))
+            .await
+            .unwrap();
+        stream.send(Event::retry(100)).await.unwrap();
+        drop(stream);
+        assert_eq!(
+            output,
+            b"id: 1\nevent: add\ndata: a\ndata: b\n\nretry: 100\n\n".to_vec()
+        );
+    }
+}